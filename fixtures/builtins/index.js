@@ -0,0 +1,6 @@
+import { Buffer } from 'buffer';
+import fs from 'fs';
+import $ from 'jquery';
+import { listen } from './server.js';
+
+console.log(Buffer, fs, $, listen);
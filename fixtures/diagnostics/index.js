@@ -0,0 +1,5 @@
+import "./b.js";
+
+const a = 1;
+let b = ;
+console.log(a, b);
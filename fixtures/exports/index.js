@@ -0,0 +1,5 @@
+import imported from 'pkg';
+const required = require('pkg');
+const url = new URL('b.js', import.meta.url);
+
+console.log(imported, required, url);
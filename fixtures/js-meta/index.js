@@ -0,0 +1,4 @@
+#!/usr/bin/env node
+import { b } from "./b.js";
+
+export const a = b + 1;
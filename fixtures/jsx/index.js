@@ -0,0 +1,5 @@
+const user = { name: 'hypersonic' };
+
+export function App() {
+  return <h1>Hello {user?.name}</h1>;
+}
@@ -0,0 +1,4 @@
+import { add } from './utils';
+import { Button } from '@/components';
+
+console.log(add(1, 2), Button);
@@ -0,0 +1,6 @@
+import fs from 'fs';
+import $ from 'jquery';
+
+export function read() {
+  return [fs, $];
+}
@@ -0,0 +1,5 @@
+import fs from 'fs';
+import $ from 'jquery';
+import { read } from './a.js';
+
+console.log(fs, $, read);
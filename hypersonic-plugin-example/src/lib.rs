@@ -0,0 +1,26 @@
+use hypersonic::core::{Asset, Content};
+use hypersonic::transformation::{Replacement, Transformer, TransformerContext, TransformerError, TransformerResult};
+
+/// Prepends a comment to every asset, as an example of a transformer loaded from `.hypersonicrc`
+pub struct BannerTransformer {}
+
+impl Transformer for BannerTransformer {
+    fn get_name(&self) -> String {
+        return String::from("BannerTransformer");
+    }
+
+    fn transform(
+        &self,
+        _: &TransformerContext,
+        asset: &mut Asset,
+    ) -> TransformerResult {
+        let content = asset.content.as_str();
+        if content.is_err() {
+            return TransformerResult::Err(TransformerError::new(&content.err().unwrap()));
+        }
+        let banner = format!("/* Built with hypersonic */\n{}", content.unwrap());
+        return TransformerResult::Replace(Replacement::new(Content::from(banner)));
+    }
+}
+
+hypersonic::export_transformer!(BannerTransformer {});
@@ -0,0 +1,61 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+
+use hypersonic::core::{Asset, AssetGraph, AssetKey, BuildMode, Content, Environment, EnvironmentContext};
+use hypersonic::platform::LogLevel;
+use hypersonic::plugins::{DynamicTransformer, PluginLibrary};
+use hypersonic::resolver::{Resolver, ResolverOptions};
+use hypersonic::transformation::{ConfigCache, Transformer, TransformerContext, TransformerResult};
+
+/// Builds this crate's cdylib, the way a user would before listing it in `.hypersonicrc`
+fn build_plugin() -> PathBuf {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let status = Command::new(env!("CARGO"))
+        .args(["build", "-p", "hypersonic-plugin-example"])
+        .current_dir(manifest_dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let target_dir = match env::var_os("CARGO_TARGET_DIR") {
+        Some(target_dir) => PathBuf::from(target_dir),
+        None => manifest_dir.join("../target"),
+    };
+    let file_name = format!("{}hypersonic_plugin_example{}", env::consts::DLL_PREFIX, env::consts::DLL_SUFFIX);
+    return target_dir.join("debug").join(file_name);
+}
+
+#[test]
+fn test_load_built_plugin() {
+    let plugin = PluginLibrary::load(&build_plugin()).unwrap();
+    assert_eq!(plugin.get_name(), "BannerTransformer");
+
+    let env = Environment::new(EnvironmentContext::Browser, BuildMode::Development);
+    let key = AssetKey {
+        file_path: PathBuf::from("/project/index.js"),
+        query: None,
+        pipeline: None,
+        unique_key: None,
+        env: env.clone(),
+    };
+    let mut asset = Asset::new(&key);
+    asset.content = Content::from(String::from("console.log(1);"));
+
+    let ctx = TransformerContext::new(
+        0,
+        env,
+        AssetGraph::new(),
+        LogLevel::Info,
+        Arc::new(Resolver::new(ResolverOptions::with_project_root(PathBuf::from("/project")))),
+        Arc::new(ConfigCache::new()),
+    );
+
+    let transformer = DynamicTransformer::new(plugin);
+    // Replace doesn't cross the plugin boundary: the response carries the new content and the
+    // host applies it once the plugin has returned, so the result is Continue with that content
+    let result = transformer.transform(&ctx, &mut asset);
+    assert!(matches!(result, TransformerResult::Continue));
+    assert_eq!(asset.content.as_bytes(), b"/* Built with hypersonic */\nconsole.log(1);");
+}
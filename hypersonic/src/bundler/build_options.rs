@@ -0,0 +1,41 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::core::BuildMode;
+use crate::platform::{LogLevel, SchedulerKind};
+use crate::resolver::ResolverOptions;
+
+#[derive(Clone, Debug)]
+pub struct BuildOptions {
+    pub entries: Vec<PathBuf>,
+    pub threads: usize,
+    pub scheduler: SchedulerKind,
+    pub mode: BuildMode,
+    pub log_level: LogLevel,
+    pub profiling: bool,
+    /// None enables source maps in development only
+    pub source_maps: Option<bool>,
+    /// Engine -> minimum version that entries are compiled for, eg "chrome" => "80"
+    pub targets: Option<BTreeMap<String, String>>,
+    pub resolver: ResolverOptions,
+}
+
+impl BuildOptions {
+    pub fn new() -> Self {
+        return BuildOptions {
+            entries: Vec::new(),
+            threads: num_cpus::get(),
+            scheduler: SchedulerKind::WorkStealing,
+            mode: BuildMode::Development,
+            log_level: LogLevel::Info,
+            profiling: false,
+            source_maps: None,
+            targets: None,
+            resolver: ResolverOptions::new(),
+        };
+    }
+
+    pub fn source_maps_enabled(&self) -> bool {
+        return self.source_maps.unwrap_or(self.mode == BuildMode::Development);
+    }
+}
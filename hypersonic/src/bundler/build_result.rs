@@ -0,0 +1,30 @@
+use std::sync::Arc;
+
+use crate::core::{Asset, AssetGraph, BuildError};
+use crate::utils::{SegmentedContainer, StandardProfiler};
+
+pub struct BuildResult {
+    pub assets: Arc<SegmentedContainer<Asset>>,
+    pub graph: Arc<AssetGraph>,
+    /// Errors and warnings
+    pub diagnostics: Vec<BuildError>,
+    pub timings: StandardProfiler,
+}
+
+impl BuildResult {
+    pub fn is_ok(&self) -> bool {
+        return self.errors().next().is_none();
+    }
+
+    pub fn is_err(&self) -> bool {
+        return !self.is_ok();
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &BuildError> {
+        return self.diagnostics.iter().filter(|diagnostic| diagnostic.is_error());
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &BuildError> {
+        return self.diagnostics.iter().filter(|diagnostic| !diagnostic.is_error());
+    }
+}
@@ -0,0 +1,638 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use dashmap::DashMap;
+
+use crate::core::{Asset, AssetGraph, AssetKey, BuildMode};
+use crate::default_plugins::default_transformers;
+use crate::platform::{LogLevel, SchedulerKind};
+use crate::resolver::ResolverOptions;
+use crate::transformation::{Transformer, TransformerContainer, transform};
+use crate::utils::{SegmentedContainer, StandardProfiler};
+
+use super::{BuildOptions, BuildResult, Reporter, ReporterEvent};
+
+/// Builds the asset graph for a set of entries.
+///
+/// ```ignore
+/// let result = Bundler::new()
+///     .entry("src/index.html")
+///     .threads(4)
+///     .build();
+/// ```
+pub struct Bundler {
+    options: BuildOptions,
+    transformers: TransformerContainer,
+    reporters: Vec<Box<dyn Reporter>>,
+}
+
+impl Bundler {
+    /// Starts with the default transformers and one thread per core
+    pub fn new() -> Self {
+        return Bundler {
+            options: BuildOptions::new(),
+            transformers: default_transformers(),
+            reporters: Vec::new(),
+        };
+    }
+
+    pub fn options(mut self, options: BuildOptions) -> Self {
+        let threads = options.threads;
+        self.options = options;
+        return self.threads(threads);
+    }
+
+    pub fn entry<P: Into<PathBuf>>(mut self, entry: P) -> Self {
+        self.options.entries.push(entry.into());
+        return self;
+    }
+
+    pub fn entries(mut self, entries: Vec<PathBuf>) -> Self {
+        self.options.entries.extend(entries);
+        return self;
+    }
+
+    /// 0 uses one thread per CPU, like the default
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.options.threads = if threads == 0 { num_cpus::get() } else { threads };
+        return self;
+    }
+
+    pub fn scheduler(mut self, scheduler: SchedulerKind) -> Self {
+        self.options.scheduler = scheduler;
+        return self;
+    }
+
+    pub fn mode(mut self, mode: BuildMode) -> Self {
+        self.options.mode = mode;
+        return self;
+    }
+
+    pub fn log_level(mut self, log_level: LogLevel) -> Self {
+        self.options.log_level = log_level;
+        return self;
+    }
+
+    pub fn profiling(mut self, profiling: bool) -> Self {
+        self.options.profiling = profiling;
+        return self;
+    }
+
+    /// Overrides the default of generating source maps in development only
+    pub fn source_maps(mut self, source_maps: bool) -> Self {
+        self.options.source_maps = Some(source_maps);
+        return self;
+    }
+
+    /// Compiles syntax the engines don't support, eg `[("chrome", "80")]`
+    pub fn targets(mut self, targets: BTreeMap<String, String>) -> Self {
+        self.options.targets = Some(targets);
+        return self;
+    }
+
+    pub fn resolver_options(mut self, resolver: ResolverOptions) -> Self {
+        self.options.resolver = resolver;
+        return self;
+    }
+
+    /// Replaces every transformer, including the defaults
+    pub fn transformers(mut self, transformers: TransformerContainer) -> Self {
+        self.transformers = transformers;
+        return self;
+    }
+
+    /// Appends a transformer to the pipeline for a pattern
+    pub fn transformer(mut self, pattern: &str, transformer: Box<dyn Transformer>) -> Self {
+        self.transformers.add(pattern, transformer);
+        return self;
+    }
+
+    pub fn reporter(mut self, reporter: Box<dyn Reporter>) -> Self {
+        self.reporters.push(reporter);
+        return self;
+    }
+
+    pub fn build(&self) -> BuildResult {
+        self.report(&ReporterEvent::BuildStart(&self.options));
+
+        let assets = SegmentedContainer::<Asset>::new();
+        let assets_index = Arc::new(DashMap::<AssetKey, usize>::new());
+        let graph = AssetGraph::new();
+        let profiler = StandardProfiler::new();
+
+        let profiler_end = profiler.start();
+
+        let diagnostics = transform(
+            &self.options,
+            &self.transformers,
+            &self.reporters,
+            assets.clone(),
+            assets_index,
+            graph.clone(),
+            &profiler,
+        );
+
+        profiler_end("build-time-total");
+
+        let result = BuildResult {
+            assets,
+            graph,
+            diagnostics,
+            timings: profiler,
+        };
+
+        self.report(&ReporterEvent::BuildEnd(&result));
+        return result;
+    }
+
+    fn report(&self, event: &ReporterEvent) {
+        for reporter in &self.reporters {
+            reporter.report(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::sync::Mutex;
+
+    use crate::core::{Asset, BuildError, BuildMode, Content, Dependency, DependencyKind, Invalidation};
+    use crate::default_plugins::{DefaultNoopTransformer, JsMeta};
+    use crate::transformation::{Replacement, TransformerContext, TransformerError, TransformerResult};
+
+    use super::*;
+
+    fn fixtures() -> PathBuf {
+        return Path::new(env!("CARGO_MANIFEST_DIR")).join("../fixtures");
+    }
+
+    struct CountingReporter {
+        actions: Arc<Mutex<usize>>,
+    }
+
+    impl Reporter for CountingReporter {
+        fn report(&self, event: &ReporterEvent) {
+            if let ReporterEvent::Action(_, _) = event {
+                *self.actions.lock().unwrap() += 1;
+            }
+        }
+    }
+
+    struct SetType(&'static str);
+
+    impl Transformer for SetType {
+        fn transform(&self, _ctx: &TransformerContext, asset: &mut Asset) -> TransformerResult {
+            asset.asset_type = self.0.to_string();
+            let content = format!("{}{}", asset.content.as_str().unwrap(), self.0);
+            asset.content = Content::from(content);
+            return TransformerResult::Continue;
+        }
+    }
+
+    /// Adds a dependency then returns the named result
+    struct Returns(&'static str);
+
+    impl Transformer for Returns {
+        fn transform(&self, ctx: &TransformerContext, asset: &mut Asset) -> TransformerResult {
+            let file_path = asset.file_path.parent().unwrap().join("scripts/index.js");
+            ctx.add_dependency(Dependency::new("./scripts/index.js", DependencyKind::Import, file_path));
+            return match self.0 {
+                "break" => TransformerResult::Break,
+                "skip" => TransformerResult::Skip,
+                "error" => TransformerResult::Err(TransformerError::new("Failed")),
+                _ => {
+                    let mut replacement = Replacement::new(Content::from(String::from("replaced")));
+                    replacement.asset_type = Some(String::from("txt"));
+                    TransformerResult::Replace(replacement)
+                }
+            };
+        }
+    }
+
+    /// Replaces the content without a source map, with the same content for None
+    struct ReplacesContent(Option<&'static str>);
+
+    impl Transformer for ReplacesContent {
+        fn transform(&self, _ctx: &TransformerContext, asset: &mut Asset) -> TransformerResult {
+            let content = match self.0 {
+                Some(content) => content.as_bytes().to_vec(),
+                None => asset.content.as_bytes().to_vec(),
+            };
+            return TransformerResult::Replace(Replacement::new(Content::from(content)));
+        }
+    }
+
+    struct LoadsTsConfig {}
+
+    impl Transformer for LoadsTsConfig {
+        fn transform(&self, ctx: &TransformerContext, asset: &mut Asset) -> TransformerResult {
+            let config = ctx.load_config(&asset.file_path, &["tsconfig.json"]).unwrap().unwrap();
+            asset.content = Content::from(config.contents["compilerOptions"]["jsx"].to_string());
+            return TransformerResult::Continue;
+        }
+    }
+
+    struct Warns {}
+
+    impl Transformer for Warns {
+        fn get_name(&self) -> String {
+            return String::from("Warns");
+        }
+
+        fn transform(&self, ctx: &TransformerContext, _asset: &mut Asset) -> TransformerResult {
+            ctx.add_warning(TransformerError::new("Looks odd"));
+            return TransformerResult::Continue;
+        }
+    }
+
+    fn build_with(html_transformers: Vec<Box<dyn Transformer>>) -> BuildResult {
+        let mut transformers = TransformerContainer::new();
+        for transformer in html_transformers {
+            transformers.add("*.html", transformer);
+        }
+        transformers.add("*.js", Box::new(DefaultNoopTransformer::new()));
+        transformers.add("*.txt", Box::new(DefaultNoopTransformer::new()));
+
+        return Bundler::new()
+            .entry(fixtures().join("basic/index.html"))
+            .scheduler(SchedulerKind::SingleThreaded)
+            .transformers(transformers)
+            .build();
+    }
+
+    #[test]
+    fn test_build_fixture() {
+        let actions = Arc::new(Mutex::new(0));
+        let result = Bundler::new()
+            .entry(fixtures().join("basic/index.html"))
+            .threads(2)
+            .scheduler(SchedulerKind::SingleThreaded)
+            .resolver_options(ResolverOptions::with_project_root(fixtures().join("basic")))
+            .reporter(Box::new(CountingReporter { actions: actions.clone() }))
+            .build();
+
+        assert!(result.is_ok());
+        assert_eq!(result.assets.len(), 3);
+        assert_eq!(result.graph.entries().len(), 1);
+        assert!(*actions.lock().unwrap() > 0);
+    }
+
+    #[test]
+    fn test_zero_threads_uses_every_cpu() {
+        let bundler = Bundler::new().threads(0);
+        assert_eq!(bundler.options.threads, num_cpus::get());
+
+        let mut options = BuildOptions::new();
+        options.threads = 0;
+        assert_eq!(Bundler::new().options(options).options.threads, num_cpus::get());
+    }
+
+    #[test]
+    fn test_build_inline_assets() {
+        let result = Bundler::new()
+            .entry(fixtures().join("inline/index.html"))
+            .scheduler(SchedulerKind::SingleThreaded)
+            .build();
+
+        assert!(result.is_ok());
+        assert_eq!(result.assets.len(), 4);
+
+        let children = result.graph.children(0);
+        let mut inline: Vec<(Option<String>, String)> = children
+            .iter()
+            .map(|id| result.assets.read(*id).unwrap())
+            .map(|asset| (asset.unique_key.clone(), asset.asset_type.clone()))
+            .collect();
+        inline.sort();
+        assert_eq!(inline, vec![
+            (Some(String::from("inline-0.css")), String::from("css")),
+            (Some(String::from("inline-1.js")), String::from("js")),
+        ]);
+    }
+
+    #[test]
+    fn test_build_missing_entry() {
+        let result = Bundler::new()
+            .entry(fixtures().join("basic/missing.html"))
+            .scheduler(SchedulerKind::SingleThreaded)
+            .build();
+
+        assert!(result.is_err());
+        assert_eq!(result.diagnostics.len(), 1);
+    }
+
+    #[test]
+    fn test_type_change_rematches() {
+        let mut transformers = TransformerContainer::new();
+        transformers.add("*.html", Box::new(SetType("md")));
+        transformers.add("*.html", Box::new(SetType("unreachable")));
+        transformers.add("*.md", Box::new(SetType("txt")));
+        transformers.add("*.txt", Box::new(SetType("txt")));
+
+        let result = Bundler::new()
+            .entry(fixtures().join("basic/index.html"))
+            .scheduler(SchedulerKind::SingleThreaded)
+            .transformers(transformers)
+            .build();
+
+        assert!(result.is_ok());
+        let asset = result.assets.read(0).unwrap();
+        assert_eq!(asset.asset_type, "txt");
+        assert_eq!(asset.previous_types, vec!["html", "md"]);
+        assert!(asset.content.as_bytes().ends_with(b"mdtxttxt"));
+    }
+
+    #[test]
+    fn test_type_change_loop() {
+        let mut transformers = TransformerContainer::new();
+        transformers.add("*.html", Box::new(SetType("md")));
+        transformers.add("*.md", Box::new(SetType("html")));
+
+        let result = Bundler::new()
+            .entry(fixtures().join("basic/index.html"))
+            .scheduler(SchedulerKind::SingleThreaded)
+            .transformers(transformers)
+            .build();
+
+        assert_eq!(result.diagnostics.len(), 1);
+        assert_eq!(result.diagnostics[0].message, "Asset type changed in a loop: html -> md -> html");
+    }
+
+    #[test]
+    fn test_break_stops_pipeline() {
+        let result = build_with(vec![Box::new(Returns("break")), Box::new(SetType("md"))]);
+        assert!(result.is_ok());
+        assert_eq!(result.assets.len(), 2);
+        assert_eq!(result.assets.read(0).unwrap().asset_type, "html");
+    }
+
+    #[test]
+    fn test_skip_drops_dependencies() {
+        let result = build_with(vec![Box::new(Returns("skip")), Box::new(SetType("txt"))]);
+        assert!(result.is_ok());
+        assert_eq!(result.assets.len(), 1);
+        assert_eq!(result.assets.read(0).unwrap().asset_type, "txt");
+        // Only the entry dependency is in the graph
+        assert_eq!(result.graph.len(), 1);
+        assert_eq!(result.graph.get_dependencies(0).len(), 0);
+    }
+
+    #[test]
+    fn test_error_drops_dependencies() {
+        let result = build_with(vec![Box::new(Returns("error"))]);
+        assert!(result.is_err());
+        assert_eq!(result.assets.len(), 1);
+        assert_eq!(result.graph.len(), 1);
+        assert_eq!(result.graph.get_dependencies(0).len(), 0);
+    }
+
+    #[test]
+    fn test_replace_content() {
+        let result = build_with(vec![Box::new(Returns("replace"))]);
+        assert!(result.is_ok());
+        assert_eq!(result.assets.len(), 2);
+        let asset = result.assets.read(0).unwrap();
+        assert_eq!(asset.asset_type, "txt");
+        assert_eq!(asset.content.as_bytes(), b"replaced");
+    }
+
+    #[test]
+    fn test_load_config_invalidates_asset() {
+        let project_root = fixtures().join("load-config").canonicalize().unwrap();
+        let mut transformers = TransformerContainer::new();
+        transformers.add("*.ts", Box::new(LoadsTsConfig {}));
+        transformers.add("*.ts", Box::new(LoadsTsConfig {}));
+
+        let result = Bundler::new()
+            .entry(project_root.join("src/nested/index.ts"))
+            .scheduler(SchedulerKind::SingleThreaded)
+            .resolver_options(ResolverOptions::with_project_root(project_root.clone()))
+            .transformers(transformers)
+            .build();
+
+        assert!(result.is_ok());
+        let asset = result.assets.read(0).unwrap();
+        assert_eq!(asset.content.as_bytes(), b"\"react-jsx\"");
+        assert_eq!(asset.invalidations, vec![
+            Invalidation::FileCreate(project_root.join("src/nested/tsconfig.json")),
+            Invalidation::FileCreate(project_root.join("src/tsconfig.json")),
+            Invalidation::FileChange(project_root.join("tsconfig.json")),
+        ]);
+    }
+
+    #[test]
+    fn test_js_meta_is_kept() {
+        let result = Bundler::new()
+            .entry(fixtures().join("js-meta/index.js"))
+            .scheduler(SchedulerKind::SingleThreaded)
+            .build();
+
+        assert!(result.is_ok());
+        let asset = result.assets.read(0).unwrap();
+        let meta = asset.meta.get::<JsMeta>().unwrap();
+        assert_eq!(meta.shebang.as_deref(), Some("/usr/bin/env node"));
+        assert!(meta.hoist_result.is_none());
+
+        let symbols = meta.symbol_result.as_ref().unwrap();
+        assert!(symbols.is_esm);
+        assert_eq!(symbols.imports.len(), 1);
+        assert_eq!(&*symbols.imports[0].source, "./b.js");
+        assert_eq!(&*symbols.exports[0].exported, "a");
+    }
+
+    #[test]
+    fn test_builtins_and_aliases() {
+        let project_root = fixtures().join("builtins").canonicalize().unwrap();
+        let result = Bundler::new()
+            .entry(project_root.join("index.js"))
+            .scheduler(SchedulerKind::SingleThreaded)
+            .resolver_options(ResolverOptions::with_project_root(project_root.clone()))
+            .build();
+
+        assert!(result.is_ok());
+        assert_eq!(result.assets.len(), 5);
+
+        let mut children: Vec<(Option<String>, PathBuf)> = result.graph
+            .children(0)
+            .iter()
+            .map(|id| result.assets.read(*id).unwrap())
+            .map(|asset| (asset.unique_key.clone(), asset.file_path.clone()))
+            .collect();
+        children.sort();
+        assert_eq!(children, vec![
+            // The browser polyfill for buffer
+            (None, project_root.join("node_modules/buffer/index.js")),
+            // Replaced by false in package.json#browser
+            (Some(String::from("empty:./server.js")), PathBuf::from("empty:./server.js")),
+            // fs has no polyfill
+            (Some(String::from("empty:fs")), PathBuf::from("empty:fs")),
+            // Aliased to a global in package.json
+            (Some(String::from("global:jquery")), PathBuf::from("global:jquery")),
+        ]);
+
+        // The edges keep the specifiers written in the code
+        let mut specifiers: Vec<String> = result.graph
+            .get_dependencies(0)
+            .into_iter()
+            .map(|dependency| dependency.specifier)
+            .collect();
+        specifiers.sort();
+        assert_eq!(specifiers, vec!["./server.js", "buffer", "fs", "jquery"]);
+    }
+
+    #[test]
+    fn test_shared_modules_are_created_once() {
+        let project_root = fixtures().join("shared-modules").canonicalize().unwrap();
+        let result = Bundler::new()
+            .entry(project_root.join("index.js"))
+            .scheduler(SchedulerKind::SingleThreaded)
+            .resolver_options(ResolverOptions::with_project_root(project_root.clone()))
+            .build();
+
+        assert!(result.is_ok());
+        // index.js, a.js and one empty fs and global jquery module for both
+        assert_eq!(result.assets.len(), 4);
+
+        let mut index_children = result.graph.children(0);
+        let a = index_children
+            .iter()
+            .position(|id| result.assets.read(*id).unwrap().file_path == project_root.join("a.js"))
+            .unwrap();
+        let a = index_children.remove(a);
+
+        let mut a_children = result.graph.children(a);
+        index_children.sort();
+        a_children.sort();
+        assert_eq!(index_children, a_children);
+    }
+
+    #[test]
+    fn test_dependency_kinds_pick_exports() {
+        let project_root = fixtures().join("exports").canonicalize().unwrap();
+        let result = Bundler::new()
+            .entry(project_root.join("index.js"))
+            .scheduler(SchedulerKind::SingleThreaded)
+            .resolver_options(ResolverOptions::with_project_root(project_root.clone()))
+            .mode(BuildMode::Production)
+            .build();
+
+        assert!(result.is_ok());
+        let mut children: Vec<PathBuf> = result.graph
+            .children(0)
+            .iter()
+            .map(|id| result.assets.read(*id).unwrap().file_path.clone())
+            .collect();
+        children.sort();
+        assert_eq!(children, vec![
+            project_root.join("b.js"),
+            project_root.join("node_modules/pkg/import.js"),
+            project_root.join("node_modules/pkg/require.js"),
+        ]);
+    }
+
+    #[test]
+    fn test_jsx_runtime_and_targets() {
+        let project_root = fixtures().join("jsx").canonicalize().unwrap();
+        let result = Bundler::new()
+            .entry(project_root.join("index.js"))
+            .scheduler(SchedulerKind::SingleThreaded)
+            .resolver_options(ResolverOptions::with_project_root(project_root.clone()))
+            .mode(BuildMode::Production)
+            .targets(BTreeMap::from([(String::from("chrome"), String::from("60"))]))
+            .build();
+
+        assert!(result.is_ok());
+        // React 18 in package.json picks the automatic runtime
+        let children: Vec<PathBuf> = result.graph
+            .children(0)
+            .iter()
+            .map(|id| result.assets.read(*id).unwrap().file_path.clone())
+            .collect();
+        assert_eq!(children, vec![project_root.join("node_modules/react/jsx-runtime.js")]);
+
+        let asset = result.assets.read(0).unwrap();
+        assert!(asset.invalidations.contains(&Invalidation::FileChange(project_root.join("package.json"))));
+        // Optional chaining is compiled for the target
+        let code = String::from_utf8_lossy(asset.content.as_bytes());
+        assert!(!code.contains("?."), "{}", code);
+    }
+
+    #[test]
+    fn test_source_maps_per_mode() {
+        let build = |mode: BuildMode, source_maps: Option<bool>| {
+            let mut bundler = Bundler::new()
+                .entry(fixtures().join("js-meta/index.js"))
+                .scheduler(SchedulerKind::SingleThreaded)
+                .resolver_options(ResolverOptions::with_project_root(fixtures().canonicalize().unwrap()))
+                .mode(mode);
+            if let Some(source_maps) = source_maps {
+                bundler = bundler.source_maps(source_maps);
+            }
+            return bundler.build();
+        };
+
+        let result = build(BuildMode::Development, None);
+        let asset = result.assets.read(0).unwrap();
+        assert!(asset.env.source_maps);
+        let map = asset.map.as_ref().unwrap();
+        assert_eq!(map.get_sources(), &vec![String::from("js-meta/index.js")]);
+
+        let result = build(BuildMode::Production, None);
+        assert!(result.assets.read(0).unwrap().map.is_none());
+
+        let result = build(BuildMode::Production, Some(true));
+        assert!(result.assets.read(0).unwrap().map.is_some());
+    }
+
+    #[test]
+    fn test_replacement_without_map_drops_stale_map() {
+        let build = |content: Option<&'static str>| {
+            return Bundler::new()
+                .entry(fixtures().join("js-meta/index.js"))
+                .scheduler(SchedulerKind::SingleThreaded)
+                .resolver_options(ResolverOptions::with_project_root(fixtures().canonicalize().unwrap()))
+                .source_maps(true)
+                .transformer("*.js", Box::new(ReplacesContent(content)))
+                .build();
+        };
+
+        let result = build(Some("replaced"));
+        assert!(result.is_ok());
+        let asset = result.assets.read(0).unwrap();
+        assert_eq!(asset.content.as_bytes(), b"replaced");
+        assert!(asset.map.is_none());
+
+        // The JS transformer's output, unchanged, still matches its map
+        let result = build(None);
+        assert!(result.assets.read(0).unwrap().map.is_some());
+    }
+
+    #[test]
+    fn test_syntax_error_fails_build() {
+        let result = Bundler::new()
+            .entry(fixtures().join("diagnostics/index.js"))
+            .scheduler(SchedulerKind::SingleThreaded)
+            .build();
+
+        assert!(result.is_err());
+        assert_eq!(result.errors().count(), 1);
+        let error = result.errors().next().unwrap();
+        assert_eq!(error.message, "Expression expected");
+        assert_eq!(error.loc.as_ref().unwrap().start_line, 4);
+        assert!(error.code.as_ref().unwrap().contains("let b = ;"));
+        // Dependencies of the broken module aren't followed
+        assert_eq!(result.assets.len(), 1);
+    }
+
+    #[test]
+    fn test_warnings_dont_fail_build() {
+        let result = build_with(vec![Box::new(Warns {})]);
+        assert!(result.is_ok());
+        let warnings: Vec<&BuildError> = result.warnings().collect();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].message, "Looks odd");
+        assert_eq!(warnings[0].transformer.as_deref(), Some("Warns"));
+    }
+}
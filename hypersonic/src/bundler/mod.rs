@@ -0,0 +1,9 @@
+mod build_options;
+mod build_result;
+mod bundler;
+mod reporter;
+
+pub use crate::bundler::build_options::*;
+pub use crate::bundler::build_result::*;
+pub use crate::bundler::bundler::*;
+pub use crate::bundler::reporter::*;
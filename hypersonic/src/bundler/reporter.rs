@@ -0,0 +1,20 @@
+use crate::transformation::{Action, PatternMatch};
+
+use super::{BuildOptions, BuildResult};
+
+pub enum ReporterEvent<'a> {
+    BuildStart(&'a BuildOptions),
+    /// An action picked up by the given worker thread
+    Action(usize, &'a Action),
+    /// The transformer pattern assigned to an asset
+    TransformersMatched(usize, &'a PatternMatch),
+    /// Asset id, transformer name and the kind of result it returned
+    TransformerResult(usize, &'a str, &'a str),
+    BuildEnd(&'a BuildResult),
+}
+
+/// Receives progress events from a build.
+/// Action events are reported from worker threads as they run
+pub trait Reporter: Send + Sync {
+    fn report(&self, event: &ReporterEvent);
+}
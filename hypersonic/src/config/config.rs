@@ -0,0 +1,499 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use json_comments::{CommentSettings, strip_comments_in_place};
+use serde_json::{Map, Value};
+
+use crate::core::BuildMode;
+use crate::default_plugins::default_pipelines;
+use crate::plugins::{DynamicTransformer, PluginLibrary, ProcessOptions, ProcessTransformer};
+use crate::transformation::{TransformerContainer, TransformerRegistry};
+use crate::utils::canonicalize_path;
+
+use super::ConfigError;
+
+pub const CONFIG_FILE_NAME: &str = ".hypersonicrc";
+
+/// Stands for the pipeline inherited from the extended config
+const SPREAD: &str = "...";
+
+/// Project configuration loaded from a `.hypersonicrc` file.
+///
+/// ```jsonc
+/// {
+///   "extends": "../.hypersonicrc",
+///   // Transformer name -> shared library exporting it, or a program to run
+///   "plugins": {
+///     "my-transformer": "./plugins/libmy_transformer.so",
+///     "my-script": { "command": ["node", "./transform.js"], "timeout": 5000 }
+///   },
+///   // Glob pattern -> transformers run in order
+///   "transformers": {
+///     "*.js": ["my-transformer", "..."]
+///   },
+///   // A boolean for every mode, or per mode
+///   "sourceMaps": { "development": true, "production": true },
+///   // Node builtin -> package replacing it in the browser, or false for an empty module
+///   "polyfills": { "crypto": false, "path": "path-browserify" },
+///   // Engine -> minimum version to compile JS syntax for
+///   "targets": { "chrome": "80", "safari": "14" }
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct HypersonicConfig {
+    pub file_path: Option<PathBuf>,
+    pub transformers: Vec<(String, Vec<String>)>,
+    pub plugins: Vec<(String, PluginSource)>,
+    /// Modes source maps are explicitly turned on or off for
+    pub source_maps: Vec<(BuildMode, bool)>,
+    /// Builtins whose polyfill is replaced, None for an empty module
+    pub polyfills: Vec<(String, Option<String>)>,
+    /// None leaves modern syntax untouched
+    pub targets: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum PluginSource {
+    /// Relative paths are resolved against the declaring file
+    Library(PathBuf),
+    /// Runs in the declaring file's directory
+    Process(ProcessOptions),
+}
+
+impl HypersonicConfig {
+    /// The built in pipelines, which every config extends
+    pub fn default() -> Self {
+        return HypersonicConfig {
+            file_path: None,
+            transformers: default_pipelines(),
+            plugins: Vec::new(),
+            source_maps: Vec::new(),
+            polyfills: Vec::new(),
+            targets: None,
+        };
+    }
+
+    pub fn load(file_path: &Path) -> Result<Self, ConfigError> {
+        let mut visited = Vec::<PathBuf>::new();
+        return load_file(file_path, HypersonicConfig::default(), &mut visited);
+    }
+
+    /// None when the config doesn't say, leaving the build's default
+    pub fn source_maps(&self, mode: &BuildMode) -> Option<bool> {
+        return self.source_maps
+            .iter()
+            .find(|(m, _)| m == mode)
+            .map(|(_, enabled)| *enabled);
+    }
+
+    fn get_pipeline(&self, pattern: &str) -> Option<&Vec<String>> {
+        return self.transformers
+            .iter()
+            .find(|(p, _)| p == pattern)
+            .map(|(_, names)| names);
+    }
+
+    fn set_pipeline(&mut self, pattern: &str, names: Vec<String>) {
+        let existing = self.transformers.iter_mut().find(|(p, _)| p == pattern);
+        if let Some((_, pipeline)) = existing {
+            *pipeline = names;
+            return;
+        }
+        self.transformers.push((pattern.to_string(), names));
+    }
+
+    /// Loads every plugin and adds it to the registry under its configured name
+    pub fn register_plugins(&self, registry: &mut TransformerRegistry) -> Result<(), ConfigError> {
+        let file_path = self.file_path.clone().unwrap_or_default();
+
+        for (name, source) in &self.plugins {
+            if registry.contains(name) {
+                return Err(ConfigError::new(&file_path, &format!("Plugin \"{}\" has the name of another transformer", name)));
+            }
+
+            let library_path = match source {
+                PluginSource::Library(library_path) => library_path,
+                PluginSource::Process(options) => {
+                    let transformer = ProcessTransformer::new(name, options.clone());
+                    registry.register(name, Box::new(move || Box::new(transformer.clone())));
+                    continue;
+                }
+            };
+
+            let plugin = PluginLibrary::load(library_path);
+            if plugin.is_err() {
+                return Err(ConfigError::new(&file_path, &format!(
+                    "Plugin \"{}\" at {}: {}",
+                    name,
+                    library_path.to_str().unwrap_or(""),
+                    plugin.err().unwrap(),
+                )));
+            }
+            let plugin = plugin.unwrap();
+            registry.register(name, Box::new(move || Box::new(DynamicTransformer::new(plugin.clone()))));
+        }
+
+        return Ok(());
+    }
+
+    /// Creates the transformers for every pipeline, failing on names the registry doesn't know
+    pub fn to_transformers(&self, registry: &TransformerRegistry) -> Result<TransformerContainer, ConfigError> {
+        let file_path = self.file_path.clone().unwrap_or_default();
+        let mut transformers = TransformerContainer::new();
+
+        for (pattern, names) in &self.transformers {
+            for name in names {
+                let transformer = registry.create(name);
+                if transformer.is_none() {
+                    return Err(ConfigError::new(&file_path, &format!(
+                        "Unknown transformer \"{}\" for \"{}\"\n\tTry {}",
+                        name,
+                        pattern,
+                        registry.names().join(", "),
+                    )));
+                }
+                transformers.add(pattern, transformer.unwrap());
+            }
+        }
+
+        return Ok(transformers);
+    }
+}
+
+fn load_file(
+    file_path: &Path,
+    base: HypersonicConfig,
+    visited: &mut Vec<PathBuf>,
+) -> Result<HypersonicConfig, ConfigError> {
+    let file_path = canonicalize_path(file_path);
+
+    if visited.contains(&file_path) {
+        return Err(ConfigError::new(&file_path, "Config extends itself"));
+    }
+    visited.push(file_path.clone());
+
+    let content = fs::read_to_string(&file_path);
+    if content.is_err() {
+        return Err(ConfigError::new(
+            &file_path,
+            &format!("Unable to read file: {}", content.err().unwrap()),
+        ));
+    }
+    let mut content = content.unwrap();
+
+    // Comments are replaced with whitespace so parse errors keep their line and column
+    if strip_comments_in_place(&mut content, CommentSettings::c_style(), true).is_err() {
+        return Err(ConfigError::new(&file_path, "Unterminated comment"));
+    }
+
+    let value: Result<Value, _> = serde_json::from_str(&content);
+    if value.is_err() {
+        return Err(ConfigError::new(&file_path, &value.err().unwrap().to_string()));
+    }
+
+    let value = value.unwrap();
+    let Value::Object(root) = value else {
+        return Err(ConfigError::new(&file_path, "Expected an object"));
+    };
+
+    for key in root.keys() {
+        if key != "extends" && key != "plugins" && key != "polyfills" && key != "sourceMaps" && key != "targets" && key != "transformers" {
+            return Err(ConfigError::new(
+                &file_path,
+                &format!("Unknown field \"{}\"\n\tTry extends, plugins, polyfills, sourceMaps, targets, transformers", key),
+            ));
+        }
+    }
+
+    let mut config = base;
+    for extends in get_extends(&root, &file_path)? {
+        config = load_file(&extends, config, visited)?;
+    }
+
+    if let Some(plugins) = root.get("plugins") {
+        let Value::Object(plugins) = plugins else {
+            return Err(ConfigError::new(&file_path, "\"plugins\" must be an object"));
+        };
+
+        for (name, plugin) in plugins {
+            let source = get_plugin_source(name, plugin, &file_path)?;
+            config.plugins.retain(|(existing, _)| existing != name);
+            config.plugins.push((name.clone(), source));
+        }
+    }
+
+    if let Some(transformers) = root.get("transformers") {
+        let Value::Object(transformers) = transformers else {
+            return Err(ConfigError::new(&file_path, "\"transformers\" must be an object"));
+        };
+
+        for (pattern, pipeline) in transformers {
+            let names = get_pipeline(pattern, pipeline, &config, &file_path)?;
+            config.set_pipeline(pattern, names);
+        }
+    }
+
+    if let Some(source_maps) = root.get("sourceMaps") {
+        for (mode, enabled) in get_source_maps(source_maps, &file_path)? {
+            config.source_maps.retain(|(existing, _)| existing != &mode);
+            config.source_maps.push((mode, enabled));
+        }
+    }
+
+    if let Some(polyfills) = root.get("polyfills") {
+        for (builtin, polyfill) in get_polyfills(polyfills, &file_path)? {
+            config.polyfills.retain(|(existing, _)| existing != &builtin);
+            config.polyfills.push((builtin, polyfill));
+        }
+    }
+
+    if let Some(targets) = root.get("targets") {
+        let merged = config.targets.get_or_insert_with(BTreeMap::new);
+        merged.extend(get_targets(targets, &file_path)?);
+    }
+
+    config.file_path = Some(file_path.clone());
+    visited.pop();
+    return Ok(config);
+}
+
+/// `extends` is a path or list of paths relative to the file declaring it
+fn get_extends(root: &Map<String, Value>, file_path: &PathBuf) -> Result<Vec<PathBuf>, ConfigError> {
+    let dir = file_path.parent().unwrap();
+
+    return match root.get("extends") {
+        None => Ok(Vec::new()),
+        Some(Value::String(extends)) => Ok(vec![dir.join(extends)]),
+        Some(Value::Array(extends)) => extends
+            .iter()
+            .map(|e| match e {
+                Value::String(e) => Ok(dir.join(e)),
+                _ => Err(ConfigError::new(file_path, "\"extends\" must only contain strings")),
+            })
+            .collect(),
+        Some(_) => Err(ConfigError::new(file_path, "\"extends\" must be a string or an array of strings")),
+    };
+}
+
+/// `sourceMaps` is a boolean for every mode, or an object of mode -> boolean
+fn get_source_maps(source_maps: &Value, file_path: &PathBuf) -> Result<Vec<(BuildMode, bool)>, ConfigError> {
+    let invalid = || ConfigError::new(
+        file_path,
+        "\"sourceMaps\" must be a boolean or { \"development\": boolean, \"production\": boolean }",
+    );
+
+    let modes = match source_maps {
+        Value::Bool(enabled) => {
+            return Ok(vec![(BuildMode::Development, *enabled), (BuildMode::Production, *enabled)]);
+        }
+        Value::Object(modes) => modes,
+        _ => return Err(invalid()),
+    };
+
+    let mut result = Vec::new();
+    for (mode, enabled) in modes {
+        let mode = match mode.as_str() {
+            "development" => BuildMode::Development,
+            "production" => BuildMode::Production,
+            _ => return Err(invalid()),
+        };
+        let Value::Bool(enabled) = enabled else {
+            return Err(invalid());
+        };
+        result.push((mode, *enabled));
+    }
+    return Ok(result);
+}
+
+/// `polyfills` is an object of builtin -> package name, or false for an empty module
+fn get_polyfills(polyfills: &Value, file_path: &PathBuf) -> Result<Vec<(String, Option<String>)>, ConfigError> {
+    let Value::Object(polyfills) = polyfills else {
+        return Err(ConfigError::new(file_path, "\"polyfills\" must be an object"));
+    };
+
+    let mut result = Vec::new();
+    for (builtin, polyfill) in polyfills {
+        let polyfill = match polyfill {
+            Value::String(polyfill) => Some(polyfill.clone()),
+            Value::Bool(false) => None,
+            _ => return Err(ConfigError::new(file_path, &format!(
+                "Polyfill for \"{}\" must be a package name or false",
+                builtin,
+            ))),
+        };
+        result.push((builtin.clone(), polyfill));
+    }
+    return Ok(result);
+}
+
+/// `targets` is an object of engine -> minimum version, eg `{ "chrome": "80" }`
+fn get_targets(targets: &Value, file_path: &PathBuf) -> Result<BTreeMap<String, String>, ConfigError> {
+    let invalid = || ConfigError::new(file_path, "\"targets\" must be an object of engine -> version, like { \"chrome\": \"80\" }");
+
+    let Value::Object(targets) = targets else {
+        return Err(invalid());
+    };
+
+    let mut result = BTreeMap::new();
+    for (engine, version) in targets {
+        let Value::String(version) = version else {
+            return Err(invalid());
+        };
+        result.insert(engine.clone(), version.clone());
+    }
+    return Ok(result);
+}
+
+fn get_plugin_source(name: &str, plugin: &Value, file_path: &PathBuf) -> Result<PluginSource, ConfigError> {
+    let dir = file_path.parent().unwrap();
+    let invalid = || ConfigError::new(file_path, &format!(
+        "Plugin \"{}\" must be a library path or {{ \"command\": [...], \"timeout\": ms }}",
+        name,
+    ));
+
+    let plugin = match plugin {
+        Value::String(library_path) => return Ok(PluginSource::Library(dir.join(library_path))),
+        Value::Object(plugin) => plugin,
+        _ => return Err(invalid()),
+    };
+
+    let Some(Value::Array(command)) = plugin.get("command") else {
+        return Err(invalid());
+    };
+    let command: Option<Vec<String>> = command.iter().map(|c| c.as_str().map(String::from)).collect();
+    if command.is_none() || command.as_ref().unwrap().len() == 0 {
+        return Err(invalid());
+    }
+
+    let mut options = ProcessOptions::new(command.unwrap());
+    options.cwd = Some(dir.to_path_buf());
+    match plugin.get("timeout") {
+        None => {}
+        Some(Value::Number(timeout)) if timeout.as_u64().is_some() => {
+            options.timeout = Duration::from_millis(timeout.as_u64().unwrap());
+        }
+        Some(_) => return Err(invalid()),
+    }
+
+    return Ok(PluginSource::Process(options));
+}
+
+fn get_pipeline(
+    pattern: &str,
+    pipeline: &Value,
+    base: &HypersonicConfig,
+    file_path: &PathBuf,
+) -> Result<Vec<String>, ConfigError> {
+    let Value::Array(pipeline) = pipeline else {
+        return Err(ConfigError::new(
+            file_path,
+            &format!("Transformers for \"{}\" must be an array of names", pattern),
+        ));
+    };
+
+    let mut names = Vec::<String>::new();
+    let mut spread = false;
+
+    for name in pipeline {
+        let Value::String(name) = name else {
+            return Err(ConfigError::new(
+                file_path,
+                &format!("Transformers for \"{}\" must be an array of names", pattern),
+            ));
+        };
+
+        if name != SPREAD {
+            names.push(name.clone());
+            continue;
+        }
+
+        if spread {
+            return Err(ConfigError::new(
+                file_path,
+                &format!("\"...\" can only be used once in \"{}\"", pattern),
+            ));
+        }
+        spread = true;
+        if let Some(inherited) = base.get_pipeline(pattern) {
+            names.extend(inherited.iter().cloned());
+        }
+    }
+
+    return Ok(names);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::default_plugins::default_registry;
+
+    use super::*;
+
+    fn fixtures() -> PathBuf {
+        return Path::new(env!("CARGO_MANIFEST_DIR")).join("../fixtures/config");
+    }
+
+    #[test]
+    fn test_extends_and_spread() {
+        let config = HypersonicConfig::load(&fixtures().join(CONFIG_FILE_NAME)).unwrap();
+
+        // base.hypersonicrc replaces *.css, .hypersonicrc spreads it
+        assert_eq!(config.get_pipeline("*.css").unwrap(), &vec![
+            String::from("hypersonic:noop"),
+            String::from("hypersonic:noop"),
+        ]);
+        // Spreading the defaults
+        assert_eq!(config.get_pipeline("*.js").unwrap(), &vec![
+            String::from("hypersonic:js"),
+            String::from("hypersonic:noop"),
+        ]);
+        // Untouched defaults are kept
+        assert_eq!(config.get_pipeline("*.html").unwrap(), &vec![String::from("hypersonic:html")]);
+        assert!(config.to_transformers(&default_registry()).is_ok());
+
+        // Turned on for every mode by base.hypersonicrc, then off for production
+        assert_eq!(config.source_maps(&BuildMode::Development), Some(true));
+        assert_eq!(config.source_maps(&BuildMode::Production), Some(false));
+
+        // Polyfills are merged per builtin
+        assert_eq!(config.polyfills, vec![
+            (String::from("crypto"), None),
+            (String::from("path"), Some(String::from("./path.js"))),
+        ]);
+
+        // Targets are merged per engine
+        assert_eq!(config.targets, Some(BTreeMap::from([
+            (String::from("chrome"), String::from("90")),
+            (String::from("safari"), String::from("14")),
+        ])));
+    }
+
+    #[test]
+    fn test_patterns_keep_declaration_order() {
+        let config = HypersonicConfig::load(&fixtures().join("order.hypersonicrc")).unwrap();
+        let transformers = config.to_transformers(&default_registry()).unwrap();
+
+        let result = transformers.match_pattern(&fixtures().join("a.md"), &fixtures(), None).unwrap();
+        assert_eq!(result.pattern, "a.m*");
+        assert_eq!(result.candidates, vec!["*.md"]);
+    }
+
+    #[test]
+    fn test_unknown_transformer() {
+        let config = HypersonicConfig::load(&fixtures().join("unknown.hypersonicrc")).unwrap();
+        let err = config.to_transformers(&default_registry()).err().unwrap();
+        assert!(err.message.starts_with("Unknown transformer \"my-transformer\" for \"*.md\""));
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let err = HypersonicConfig::load(&fixtures().join("invalid.hypersonicrc")).err().unwrap();
+        assert!(err.message.contains("line 4"));
+
+        let err = HypersonicConfig::load(&fixtures().join("cycle.hypersonicrc")).err().unwrap();
+        assert_eq!(err.message, "Config extends itself");
+
+        assert!(HypersonicConfig::load(&fixtures().join("missing.hypersonicrc")).is_err());
+    }
+}
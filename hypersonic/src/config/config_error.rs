@@ -0,0 +1,25 @@
+use std::fmt;
+use std::path::PathBuf;
+
+#[derive(Clone, Debug)]
+pub struct ConfigError {
+    pub file_path: PathBuf,
+    pub message: String,
+}
+
+impl ConfigError {
+    pub fn new(file_path: &PathBuf, message: &str) -> Self {
+        return ConfigError {
+            file_path: file_path.clone(),
+            message: message.to_string(),
+        };
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid config: {}", self.message)?;
+        write!(f, "\n  at {}", self.file_path.to_str().unwrap_or(""))?;
+        return Ok(());
+    }
+}
@@ -0,0 +1,5 @@
+mod config;
+mod config_error;
+
+pub use crate::config::config::*;
+pub use crate::config::config_error::*;
@@ -1,8 +1,64 @@
 use std::path::PathBuf;
 
+use super::{AssetMeta, Content, Environment, Invalidation, SourceMap};
+
 pub struct Asset {
   pub file_path: PathBuf,
+  pub query: Option<String>,
+  pub env: Environment,
+  pub pipeline: Option<String>,
+  /// Set for inline assets, which take `file_path` from their parent (or a virtual one,
+  /// like `empty:fs` for stand-in modules) and have no file of their own
+  pub unique_key: Option<String>,
+  /// Usually the extension, transformers can change it to move the asset
+  /// onto the pipeline for another type, eg `ts` to `js`
+  pub asset_type: String,
+  /// Types the asset had before its current one
+  pub previous_types: Vec<String>,
   pub transformer_pattern: String,
-  pub content: String,
+  pub content: Content,
   pub content_hash: String,
+  /// Maps `content` back to the original file, when source maps are enabled
+  pub map: Option<SourceMap>,
+  /// Files the transformers read or looked for, other than the asset itself
+  pub invalidations: Vec<Invalidation>,
+  /// What transformers learned about the asset, eg the JS transformer's `JsMeta`
+  pub meta: AssetMeta,
+}
+
+impl Asset {
+  pub fn new(key: &AssetKey) -> Self {
+    return Asset {
+      file_path: key.file_path.clone(),
+      query: key.query.clone(),
+      env: key.env.clone(),
+      pipeline: key.pipeline.clone(),
+      unique_key: key.unique_key.clone(),
+      asset_type: get_asset_type(&key.file_path),
+      previous_types: Vec::new(),
+      transformer_pattern: String::from(""),
+      content: Content::new(),
+      content_hash: String::from(""),
+      map: None,
+      invalidations: Vec::new(),
+      meta: AssetMeta::new(),
+    };
+  }
+}
+
+fn get_asset_type(file_path: &PathBuf) -> String {
+  return file_path
+    .extension()
+    .map(|extension| extension.to_string_lossy().to_string())
+    .unwrap_or_default();
+}
+
+/// Identity of an asset, two dependencies with the same key share one asset
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AssetKey {
+  pub file_path: PathBuf,
+  pub query: Option<String>,
+  pub env: Environment,
+  pub pipeline: Option<String>,
+  pub unique_key: Option<String>,
 }
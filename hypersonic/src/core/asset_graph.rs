@@ -0,0 +1,153 @@
+use std::sync::{Arc, RwLock};
+
+use dashmap::DashMap;
+
+use super::Dependency;
+
+/// Dependency edges between the assets held in the asset container.
+/// Nodes are referenced by their asset id, edges by their dependency id
+pub struct AssetGraph {
+  dependencies: RwLock<Vec<Dependency>>,
+  entries: RwLock<Vec<usize>>,
+  outgoing: DashMap<usize, Vec<usize>>,
+  incoming: DashMap<usize, Vec<usize>>,
+}
+
+impl AssetGraph {
+  pub fn new() -> Arc<AssetGraph> {
+    return Arc::new(AssetGraph {
+      dependencies: RwLock::new(Vec::new()),
+      entries: RwLock::new(Vec::new()),
+      outgoing: DashMap::new(),
+      incoming: DashMap::new(),
+    });
+  }
+
+  pub fn add_dependency(&self, dependency: Dependency) -> usize {
+    let source_asset_id = dependency.source_asset_id;
+
+    let mut dependencies = self.dependencies.write().unwrap();
+    let id = dependencies.len();
+    dependencies.push(dependency);
+    drop(dependencies);
+
+    if let Some(source_asset_id) = source_asset_id {
+      self.outgoing.entry(source_asset_id).or_default().push(id);
+    }
+    return id;
+  }
+
+  pub fn add_entry(&self, dependency: Dependency) -> usize {
+    let id = self.add_dependency(dependency);
+    self.entries.write().unwrap().push(id);
+    return id;
+  }
+
+  pub fn resolve_dependency(&self, dependency_id: usize, asset_id: usize) {
+    let mut dependencies = self.dependencies.write().unwrap();
+    dependencies[dependency_id].resolved_asset_id = Some(asset_id);
+    drop(dependencies);
+
+    self.incoming.entry(asset_id).or_default().push(dependency_id);
+  }
+
+  pub fn get_dependency(&self, dependency_id: usize) -> Dependency {
+    let dependencies = self.dependencies.read().unwrap();
+    return dependencies[dependency_id].clone();
+  }
+
+  pub fn len(&self) -> usize {
+    return self.dependencies.read().unwrap().len();
+  }
+
+  /// Asset ids of the entry assets
+  pub fn entries(&self) -> Vec<usize> {
+    let dependencies = self.dependencies.read().unwrap();
+    let entries = self.entries.read().unwrap();
+    return entries
+      .iter()
+      .filter_map(|id| dependencies[*id].resolved_asset_id)
+      .collect();
+  }
+
+  /// Dependencies requested by an asset, in the order they were added
+  pub fn get_dependencies(&self, asset_id: usize) -> Vec<Dependency> {
+    return self.collect_edges(&self.outgoing, asset_id);
+  }
+
+  /// Dependencies that resolved to an asset
+  pub fn get_incoming_dependencies(&self, asset_id: usize) -> Vec<Dependency> {
+    return self.collect_edges(&self.incoming, asset_id);
+  }
+
+  /// Asset ids an asset depends on
+  pub fn children(&self, asset_id: usize) -> Vec<usize> {
+    let mut children = Vec::<usize>::new();
+    for dependency in self.get_dependencies(asset_id) {
+      if let Some(id) = dependency.resolved_asset_id {
+        if !children.contains(&id) {
+          children.push(id);
+        }
+      }
+    }
+    return children;
+  }
+
+  /// Asset ids that depend on an asset
+  pub fn parents(&self, asset_id: usize) -> Vec<usize> {
+    let mut parents = Vec::<usize>::new();
+    for dependency in self.get_incoming_dependencies(asset_id) {
+      if let Some(id) = dependency.source_asset_id {
+        if !parents.contains(&id) {
+          parents.push(id);
+        }
+      }
+    }
+    return parents;
+  }
+
+  fn collect_edges(&self, edges: &DashMap<usize, Vec<usize>>, asset_id: usize) -> Vec<Dependency> {
+    let ids = match edges.get(&asset_id) {
+      Some(ids) => ids.clone(),
+      None => return Vec::new(),
+    };
+    let dependencies = self.dependencies.read().unwrap();
+    return ids.iter().map(|id| dependencies[*id].clone()).collect();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::PathBuf;
+
+  use super::*;
+  use crate::core::DependencyKind;
+
+  #[test]
+  fn test_edges() {
+    let graph = AssetGraph::new();
+
+    let entry = graph.add_entry(Dependency::new("index.html", DependencyKind::Entry, PathBuf::from("/index.html")));
+    graph.resolve_dependency(entry, 0);
+
+    for (specifier, asset_id) in [("./a.js", 1), ("./b.js", 2)] {
+      let mut dependency = Dependency::new(specifier, DependencyKind::Url, PathBuf::from(specifier));
+      dependency.source_asset_id = Some(0);
+      let id = graph.add_dependency(dependency);
+      graph.resolve_dependency(id, asset_id);
+    }
+
+    let mut dependency = Dependency::new("./b.js", DependencyKind::Import, PathBuf::from("./b.js"));
+    dependency.source_asset_id = Some(1);
+    let id = graph.add_dependency(dependency);
+    graph.resolve_dependency(id, 2);
+
+    assert_eq!(graph.len(), 4);
+    assert_eq!(graph.entries(), vec![0]);
+    assert_eq!(graph.children(0), vec![1, 2]);
+    assert_eq!(graph.children(2), Vec::<usize>::new());
+    assert_eq!(graph.parents(2), vec![0, 1]);
+    assert_eq!(graph.parents(0), Vec::<usize>::new());
+    assert_eq!(graph.get_dependencies(1)[0].kind, DependencyKind::Import);
+  }
+}
@@ -0,0 +1,85 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+/// Values transformers attach to an asset for later stages to read.
+/// Entries are keyed by their type, so a transformer defines a type for what
+/// it stores and anything that knows the type can get it back
+#[derive(Default)]
+pub struct AssetMeta {
+  values: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl AssetMeta {
+  pub fn new() -> Self {
+    return AssetMeta {
+      values: HashMap::new(),
+    };
+  }
+
+  /// Stores the value, returning the one it replaced
+  pub fn insert<T: Any + Send + Sync>(&mut self, value: T) -> Option<T> {
+    return self.values
+      .insert(TypeId::of::<T>(), Box::new(value))
+      .map(|previous| *previous.downcast::<T>().unwrap());
+  }
+
+  pub fn get<T: Any + Send + Sync>(&self) -> Option<&T> {
+    return self.values
+      .get(&TypeId::of::<T>())
+      .map(|value| value.downcast_ref::<T>().unwrap());
+  }
+
+  pub fn get_mut<T: Any + Send + Sync>(&mut self) -> Option<&mut T> {
+    return self.values
+      .get_mut(&TypeId::of::<T>())
+      .map(|value| value.downcast_mut::<T>().unwrap());
+  }
+
+  pub fn remove<T: Any + Send + Sync>(&mut self) -> Option<T> {
+    return self.values
+      .remove(&TypeId::of::<T>())
+      .map(|value| *value.downcast::<T>().unwrap());
+  }
+
+  pub fn contains<T: Any + Send + Sync>(&self) -> bool {
+    return self.values.contains_key(&TypeId::of::<T>());
+  }
+
+  pub fn len(&self) -> usize {
+    return self.values.len();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[derive(Debug, PartialEq)]
+  struct Shebang(String);
+
+  #[derive(Debug, PartialEq)]
+  struct IsConstant(bool);
+
+  #[test]
+  fn test_entries_are_keyed_by_type() {
+    let mut meta = AssetMeta::new();
+    assert!(meta.insert(Shebang(String::from("#!/usr/bin/env node"))).is_none());
+    meta.insert(IsConstant(true));
+
+    assert_eq!(meta.len(), 2);
+    assert_eq!(meta.get::<Shebang>(), Some(&Shebang(String::from("#!/usr/bin/env node"))));
+    assert_eq!(meta.get::<IsConstant>(), Some(&IsConstant(true)));
+    assert!(meta.get::<String>().is_none());
+  }
+
+  #[test]
+  fn test_replace_and_remove() {
+    let mut meta = AssetMeta::new();
+    meta.insert(IsConstant(false));
+    meta.get_mut::<IsConstant>().unwrap().0 = true;
+    assert_eq!(meta.insert(IsConstant(false)), Some(IsConstant(true)));
+
+    assert_eq!(meta.remove::<IsConstant>(), Some(IsConstant(false)));
+    assert!(!meta.contains::<IsConstant>());
+  }
+}
@@ -0,0 +1,92 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use super::SourceLocation;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BuildStage {
+  ReadContents,
+  AssignTransformers,
+  TransformContents,
+}
+
+/// Errors fail the build, warnings are only reported
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Severity {
+  Error,
+  Warning,
+}
+
+/// A range to point at in a code frame, with an optional note
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeHighlight {
+  pub message: Option<String>,
+  pub loc: SourceLocation,
+}
+
+#[derive(Clone, Debug)]
+pub struct BuildError {
+  pub file_path: PathBuf,
+  pub stage: BuildStage,
+  pub severity: Severity,
+  pub transformer: Option<String>,
+  pub message: String,
+  pub loc: Option<SourceLocation>,
+  /// Ranges to show in the code frame, `loc` is shown when there are none
+  pub highlights: Vec<CodeHighlight>,
+  pub hints: Vec<String>,
+  pub documentation_url: Option<String>,
+  /// The content the locations point into, as the transformer saw it
+  pub code: Option<String>,
+}
+
+impl BuildError {
+  pub fn new(file_path: &PathBuf, stage: BuildStage, message: &str) -> Self {
+    return BuildError {
+      file_path: file_path.clone(),
+      stage,
+      severity: Severity::Error,
+      transformer: None,
+      message: message.to_string(),
+      loc: None,
+      highlights: Vec::new(),
+      hints: Vec::new(),
+      documentation_url: None,
+      code: None,
+    };
+  }
+
+  pub fn is_error(&self) -> bool {
+    return self.severity == Severity::Error;
+  }
+
+  /// Where the code frame points, the highlights or otherwise `loc`
+  pub fn get_highlights(&self) -> Vec<CodeHighlight> {
+    if self.highlights.len() != 0 {
+      return self.highlights.clone();
+    }
+    return self.loc
+      .iter()
+      .map(|loc| CodeHighlight { message: None, loc: loc.clone() })
+      .collect();
+  }
+}
+
+impl fmt::Display for BuildError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{:?}", self.stage)?;
+    if let Some(transformer) = &self.transformer {
+      write!(f, " ({})", transformer)?;
+    }
+    write!(f, ": {}", self.message)?;
+    write!(f, "\n  at {}", self.file_path.to_str().unwrap_or(""))?;
+    if let Some(loc) = &self.loc {
+      write!(f, ":{}:{}", loc.start_line, loc.start_col)?;
+    }
+    return Ok(());
+  }
+}
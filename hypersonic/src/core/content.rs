@@ -0,0 +1,123 @@
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+use std::str;
+
+use memmap2::Mmap;
+
+/// Files at least this large are memory mapped instead of read into memory
+pub const MMAP_THRESHOLD: u64 = 1024 * 1024;
+
+/// Contents of an asset. Files are read as bytes and only decoded
+/// when a transformer asks for text, so binary assets pass through untouched
+pub enum Content {
+  Text(String),
+  Bytes(Vec<u8>),
+  Mapped(Mmap),
+}
+
+impl Content {
+  pub fn new() -> Self {
+    return Content::Bytes(Vec::new());
+  }
+
+  /// Reads a file, memory mapping it when it's large
+  pub fn read(file_path: &Path) -> io::Result<Self> {
+    let file = File::open(file_path)?;
+    if file.metadata()?.len() < MMAP_THRESHOLD {
+      return Ok(Content::Bytes(fs::read(file_path)?));
+    }
+    // Safety: the mapping is read only, a file changing while it is mapped
+    // gives inconsistent content, the same as changing while being read
+    let mapped = unsafe { Mmap::map(&file)? };
+    return Ok(Content::Mapped(mapped));
+  }
+
+  pub fn as_bytes(&self) -> &[u8] {
+    return match self {
+      Content::Text(text) => text.as_bytes(),
+      Content::Bytes(bytes) => bytes,
+      Content::Mapped(mapped) => mapped,
+    };
+  }
+
+  /// Decodes the content as UTF-8 the first time it is called, the text is kept for later calls
+  pub fn as_str(&mut self) -> Result<&str, String> {
+    if let Content::Text(text) = self {
+      return Ok(text);
+    }
+
+    let decoded = str::from_utf8(self.as_bytes());
+    if decoded.is_err() {
+      return Err(format!("Content is not valid UTF-8: {}", decoded.err().unwrap()));
+    }
+    *self = Content::Text(decoded.unwrap().to_string());
+    return self.as_str();
+  }
+
+  pub fn len(&self) -> usize {
+    return self.as_bytes().len();
+  }
+
+  pub fn is_text(&self) -> bool {
+    return match self {
+      Content::Text(_) => true,
+      _ => false,
+    };
+  }
+}
+
+impl From<String> for Content {
+  fn from(text: String) -> Self {
+    return Content::Text(text);
+  }
+}
+
+impl From<Vec<u8>> for Content {
+  fn from(bytes: Vec<u8>) -> Self {
+    return Content::Bytes(bytes);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{env, process};
+
+  use super::*;
+
+  #[test]
+  fn test_lazy_decoding() {
+    let mut content = Content::from("héllo".as_bytes().to_vec());
+    assert!(!content.is_text());
+    assert_eq!(content.as_str().unwrap(), "héllo");
+    assert!(content.is_text());
+    assert_eq!(content.len(), 6);
+  }
+
+  #[test]
+  fn test_binary_content() {
+    let mut content = Content::from(vec![0x89, 0x50, 0x4e, 0x47, 0xff]);
+    assert!(content.as_str().is_err());
+    assert_eq!(content.as_bytes(), &[0x89, 0x50, 0x4e, 0x47, 0xff]);
+  }
+
+  #[test]
+  fn test_read_large_file() {
+    // Unique per process so concurrent test runs don't share the file
+    let file_path = env::temp_dir().join(format!("hypersonic-content-large-{}.bin", process::id()));
+    fs::write(&file_path, vec![7u8; MMAP_THRESHOLD as usize]).unwrap();
+
+    let content = Content::read(&file_path).unwrap();
+    let is_mapped = matches!(content, Content::Mapped(_));
+    let len = content.len();
+    let is_unchanged = content.as_bytes().iter().all(|b| *b == 7);
+
+    // Removed before asserting, so a failure doesn't leave it behind
+    drop(content);
+    fs::remove_file(&file_path).unwrap();
+
+    assert!(is_mapped);
+    assert_eq!(len, MMAP_THRESHOLD as usize);
+    assert!(is_unchanged);
+  }
+}
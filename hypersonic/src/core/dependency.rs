@@ -0,0 +1,61 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Environment, SourceLocation};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DependencyKind {
+  Entry,
+  Import,
+  Export,
+  DynamicImport,
+  Require,
+  WebWorker,
+  ServiceWorker,
+  Worklet,
+  Url,
+  File,
+  /// A child asset whose content was taken from the source asset
+  Inline,
+}
+
+/// An edge in the asset graph from the asset that requested it
+/// (none for entries) to the asset the specifier resolved to
+#[derive(Clone, Debug)]
+pub struct Dependency {
+  pub specifier: String,
+  pub kind: DependencyKind,
+  pub loc: Option<SourceLocation>,
+  pub file_path: PathBuf,
+  pub query: Option<String>,
+  /// Named pipeline from the specifier prefix, eg `url` for `url:./logo.png`
+  pub pipeline: Option<String>,
+  /// Set when the dependency points to an inline asset within `file_path`
+  pub unique_key: Option<String>,
+  /// None inherits the environment of the source asset
+  pub env: Option<Environment>,
+  /// Left for the runtime to load, like node builtins in node builds, so no asset is created for it
+  pub is_external: bool,
+  pub source_asset_id: Option<usize>,
+  pub resolved_asset_id: Option<usize>,
+}
+
+impl Dependency {
+  pub fn new(specifier: &str, kind: DependencyKind, file_path: PathBuf) -> Self {
+    return Dependency {
+      specifier: specifier.to_string(),
+      kind,
+      loc: None,
+      file_path,
+      query: None,
+      pipeline: None,
+      unique_key: None,
+      env: None,
+      is_external: false,
+      source_asset_id: None,
+      resolved_asset_id: None,
+    };
+  }
+}
@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::DependencyKind;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EnvironmentContext {
+  Browser,
+  WebWorker,
+  ServiceWorker,
+  Node,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+  EsModule,
+  CommonJS,
+  Global,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SourceType {
+  Module,
+  Script,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BuildMode {
+  Development,
+  Production,
+}
+
+/// The runtime an asset is compiled for. The same file can be
+/// included once per environment it is reached from
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Environment {
+  pub context: EnvironmentContext,
+  /// Minimum engine versions to compile syntax for, e.g. "chrome" => "80".
+  /// None leaves modern syntax untouched
+  pub targets: Option<BTreeMap<String, String>>,
+  pub output_format: OutputFormat,
+  pub source_type: SourceType,
+  pub mode: BuildMode,
+  /// Whether transformers should produce source maps
+  pub source_maps: bool,
+}
+
+impl Environment {
+  pub fn new(context: EnvironmentContext, mode: BuildMode) -> Self {
+    let output_format = match context {
+      EnvironmentContext::Node => OutputFormat::CommonJS,
+      _ => OutputFormat::EsModule,
+    };
+
+    return Environment {
+      source_maps: mode == BuildMode::Development,
+      context,
+      targets: None,
+      output_format,
+      source_type: SourceType::Module,
+      mode,
+    };
+  }
+
+  pub fn is_browser(&self) -> bool {
+    return match self.context {
+      EnvironmentContext::Browser => true,
+      EnvironmentContext::WebWorker => true,
+      EnvironmentContext::ServiceWorker => true,
+      EnvironmentContext::Node => false,
+    };
+  }
+
+  pub fn is_worker(&self) -> bool {
+    return match self.context {
+      EnvironmentContext::WebWorker => true,
+      EnvironmentContext::ServiceWorker => true,
+      _ => false,
+    };
+  }
+
+  pub fn is_node(&self) -> bool {
+    return self.context == EnvironmentContext::Node;
+  }
+
+  pub fn is_development(&self) -> bool {
+    return self.mode == BuildMode::Development;
+  }
+
+  /// The environment a dependency of this kind runs in. Everything is
+  /// inherited except at worker boundaries, which start a new context
+  pub fn for_dependency(&self, kind: &DependencyKind) -> Environment {
+    let mut env = self.clone();
+    match kind {
+      DependencyKind::WebWorker => env.context = EnvironmentContext::WebWorker,
+      DependencyKind::ServiceWorker => env.context = EnvironmentContext::ServiceWorker,
+      _ => {}
+    }
+    return env;
+  }
+}
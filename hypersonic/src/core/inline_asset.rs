@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+use super::{DependencyKind, Environment};
+
+/// An asset whose content comes from its parent rather than a file,
+/// eg the body of an inline `<script>`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InlineAsset {
+  /// Identifies the child within its parent file, so it gets the same asset every build
+  pub unique_key: String,
+  pub asset_type: String,
+  pub content: String,
+  pub kind: DependencyKind,
+  /// None inherits the environment of the parent asset
+  pub env: Option<Environment>,
+}
+
+impl InlineAsset {
+  pub fn new(unique_key: &str, asset_type: &str, content: &str) -> Self {
+    return InlineAsset {
+      unique_key: unique_key.to_string(),
+      asset_type: asset_type.to_string(),
+      content: content.to_string(),
+      kind: DependencyKind::Inline,
+      env: None,
+    };
+  }
+}
@@ -0,0 +1,13 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// A change on disk that makes a transformed asset out of date
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Invalidation {
+  /// The file was read, editing or deleting it changes the result
+  FileChange(PathBuf),
+  /// The file was looked for and didn't exist, creating it changes the result
+  FileCreate(PathBuf),
+}
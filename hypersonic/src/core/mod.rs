@@ -1,3 +1,25 @@
 mod asset;
+mod asset_graph;
+mod asset_meta;
+mod build_error;
+mod content;
+mod dependency;
+mod environment;
+mod inline_asset;
+mod invalidation;
+mod pipeline;
+mod source_map;
+mod source_location;
 
 pub use crate::core::asset::*;
+pub use crate::core::asset_graph::*;
+pub use crate::core::asset_meta::*;
+pub use crate::core::build_error::*;
+pub use crate::core::content::*;
+pub use crate::core::dependency::*;
+pub use crate::core::environment::*;
+pub use crate::core::inline_asset::*;
+pub use crate::core::invalidation::*;
+pub use crate::core::pipeline::*;
+pub use crate::core::source_map::*;
+pub use crate::core::source_location::*;
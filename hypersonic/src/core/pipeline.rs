@@ -0,0 +1,13 @@
+/// Named pipelines a specifier or transformer pattern can be prefixed with, eg `url:./logo.png`
+pub const NAMED_PIPELINES: [&str; 3] = ["url", "raw", "inline"];
+
+/// Splits a known pipeline prefix off a specifier or pattern.
+/// Other prefixes such as `node:` are left alone
+pub fn split_pipeline(specifier: &str) -> (Option<String>, &str) {
+  if let Some((pipeline, rest)) = specifier.split_once(':') {
+    if NAMED_PIPELINES.contains(&pipeline) {
+      return (Some(pipeline.to_string()), rest);
+    }
+  }
+  return (None, specifier);
+}
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+/// 1-based line and column range within an asset's source, end exclusive
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceLocation {
+  pub start_line: usize,
+  pub start_col: usize,
+  pub end_line: usize,
+  pub end_col: usize,
+}
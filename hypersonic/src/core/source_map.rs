@@ -0,0 +1,51 @@
+pub use parcel_sourcemap::SourceMap;
+
+/// Chains the map a transformer returned onto the map the asset already had.
+/// `next` maps the new content to the content before the transformer ran,
+/// the result maps it all the way back to the original file
+pub fn compose_source_maps(previous: Option<SourceMap>, next: SourceMap) -> Result<SourceMap, String> {
+  let mut next = next;
+  let Some(mut previous) = previous else {
+    return Ok(next);
+  };
+
+  let result = next.extends(&mut previous);
+  if result.is_err() {
+    return Err(format!("Unable to compose source maps: {}", result.err().unwrap()));
+  }
+  return Ok(next);
+}
+
+#[cfg(test)]
+mod tests {
+  use parcel_sourcemap::OriginalLocation;
+
+  use super::*;
+
+  #[test]
+  fn test_compose_maps_to_original() {
+    // A first transformer moved `a` from column 0 to column 10
+    let mut previous = SourceMap::new("/project");
+    let source = previous.add_source("/project/index.ts");
+    previous.add_mapping(0, 10, Some(OriginalLocation::new(0, 0, source, None)));
+
+    // A second transformer then moved it down a line
+    let mut next = SourceMap::new("/project");
+    let source = next.add_source("/project/index.ts");
+    next.add_mapping(1, 0, Some(OriginalLocation::new(0, 10, source, None)));
+
+    let mut composed = compose_source_maps(Some(previous), next).unwrap();
+    let mapping = composed.find_closest_mapping(1, 0).unwrap();
+    let original = mapping.original.unwrap();
+    assert_eq!((original.original_line, original.original_column), (0, 0));
+    assert_eq!(composed.get_source(original.source).unwrap(), "index.ts");
+  }
+
+  #[test]
+  fn test_first_map_is_kept() {
+    let mut next = SourceMap::new("/project");
+    next.add_mapping(0, 0, None);
+    let composed = compose_source_maps(None, next).unwrap();
+    assert_eq!(composed.get_mappings().len(), 1);
+  }
+}
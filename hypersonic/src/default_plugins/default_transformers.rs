@@ -0,0 +1,41 @@
+use crate::config::HypersonicConfig;
+use crate::transformation::{TransformerContainer, TransformerRegistry};
+
+use super::{DefaultHTMLTransformer, DefaultJSTransformer, DefaultNoopTransformer};
+
+/// Built in transformers, by the name used to refer to them in `.hypersonicrc`
+pub fn default_registry() -> TransformerRegistry {
+    let mut registry = TransformerRegistry::new();
+
+    registry.register("hypersonic:html", Box::new(|| Box::new(DefaultHTMLTransformer::new())));
+    registry.register("hypersonic:js", Box::new(|| Box::new(DefaultJSTransformer::new(false, false))));
+    registry.register("hypersonic:jsx", Box::new(|| Box::new(DefaultJSTransformer::new(true, false))));
+    registry.register("hypersonic:ts", Box::new(|| Box::new(DefaultJSTransformer::new(false, true))));
+    registry.register("hypersonic:tsx", Box::new(|| Box::new(DefaultJSTransformer::new(true, true))));
+    registry.register("hypersonic:noop", Box::new(|| Box::new(DefaultNoopTransformer::new())));
+
+    return registry;
+}
+
+/// Pipelines used when there is no config, and what `"..."` expands to in a config
+pub fn default_pipelines() -> Vec<(String, Vec<String>)> {
+    return vec![
+        (String::from("*.html"), vec![String::from("hypersonic:html")]),
+        (String::from("*.js"), vec![String::from("hypersonic:js")]),
+        (String::from("*.jsx"), vec![String::from("hypersonic:jsx")]),
+        (String::from("*.ts"), vec![String::from("hypersonic:ts")]),
+        (String::from("*.tsx"), vec![String::from("hypersonic:tsx")]),
+        (String::from("*.css"), vec![String::from("hypersonic:noop")]),
+        // Binary files are passed through as is
+        (String::from("*.{png,jpg,jpeg,gif,webp,avif,ico,woff,woff2,ttf,otf,eot,wasm}"), vec![String::from("hypersonic:noop")]),
+        (String::from("url:*"), vec![String::from("hypersonic:noop")]),
+        (String::from("raw:*"), vec![String::from("hypersonic:noop")]),
+    ];
+}
+
+/// Transformers used when the caller doesn't supply its own
+pub fn default_transformers() -> TransformerContainer {
+    return HypersonicConfig::default()
+        .to_transformers(&default_registry())
+        .unwrap();
+}
@@ -1,7 +1,9 @@
+mod default_transformers;
 mod transformer_noop;
 mod transformer_html;
 mod transformer_js;
 
+pub use crate::default_plugins::default_transformers::*;
 pub use crate::default_plugins::transformer_html::*;
 pub use crate::default_plugins::transformer_js::*;
 pub use crate::default_plugins::transformer_noop::*;
@@ -3,8 +3,8 @@ extern crate markup5ever_rcdom as rcdom;
 
 use std::path::Path;
 
-use crate::core::Asset;
-use crate::transformation::{Transformer, TransformerContext, TransformerResult};
+use crate::core::{Asset, Dependency, DependencyKind, InlineAsset, split_pipeline};
+use crate::transformation::{Transformer, TransformerContext, TransformerError, TransformerResult};
 
 use html5ever::parse_document;
 use html5ever::tendril::TendrilSink;
@@ -28,36 +28,64 @@ impl Transformer for DefaultHTMLTransformer {
         ctx: &TransformerContext,
         asset: &mut Asset,
     ) -> TransformerResult {
-        let result = get_script_src_attrs(&asset.content);
+        let html = asset.content.as_str();
+        if html.is_err() {
+            return TransformerResult::Err(TransformerError::new(&html.err().unwrap()));
+        }
+
+        let result = get_references(html.unwrap());
         if result.is_err() {
-            return TransformerResult::Break;
+            return TransformerResult::Err(TransformerError::new("Unable to parse HTML"));
         }
+        let references = result.unwrap();
 
-        for specifier in result.unwrap() {
+        for specifier in references.script_srcs {
+          let (pipeline, path) = split_pipeline(&specifier);
+          let (path, query) = match path.split_once('?') {
+            Some((path, query)) => (path, Some(query.to_string())),
+            None => (path, None),
+          };
           let asset_dir_path = asset.file_path.parent().unwrap();
-          let parsed_specifier = Path::new(&specifier);
+          let parsed_specifier = Path::new(path);
           let full_path = asset_dir_path.join(parsed_specifier);
-          ctx.add_dependency(full_path);
+          let mut dependency = Dependency::new(&specifier, DependencyKind::Url, full_path);
+          dependency.query = query;
+          dependency.pipeline = pipeline;
+          ctx.add_dependency(dependency);
+        }
+
+        for (index, (asset_type, content)) in references.inline.iter().enumerate() {
+          let unique_key = format!("inline-{}.{}", index, asset_type);
+          ctx.add_inline_asset(&asset.file_path, InlineAsset::new(&unique_key, asset_type, content));
         }
 
         return TransformerResult::Continue;
     }
 }
 
-fn get_script_src_attrs(html: &str) -> Result<Vec<String>, ()> {
-    let mut script_src_attrs = Vec::<String>::new();
+struct References {
+    script_srcs: Vec<String>,
+    /// Type and content of each inline `<script>` and `<style>`
+    inline: Vec<(String, String)>,
+}
+
+fn get_references(html: &str) -> Result<References, ()> {
+    let mut references = References {
+        script_srcs: Vec::new(),
+        inline: Vec::new(),
+    };
 
     let dom = parse_document(RcDom::default(), Default::default())
         .from_utf8()
         .read_from(&mut html.as_bytes())
         .unwrap();
 
-    walk(&dom.document, &mut script_src_attrs);
+    walk(&dom.document, &mut references);
 
-    return Ok(script_src_attrs);
+    return Ok(references);
 }
 
-fn walk(handle: &Handle, attrs_list: &mut Vec<String>) {
+fn walk(handle: &Handle, references: &mut References) {
     let node = handle;
     match node.data {
         NodeData::Element {
@@ -65,19 +93,50 @@ fn walk(handle: &Handle, attrs_list: &mut Vec<String>) {
             ref attrs,
             ..
         } => {
-            if name.local.to_string() == "script" {
-                for attr in attrs.borrow().iter() {
-                    if attr.name.local.to_string() == "src" {
-                        attrs_list.push(attr.value.to_string());
-                        break;
-                    }
+            let tag = name.local.to_string();
+            let mut src = None;
+            let mut script_type = None;
+            for attr in attrs.borrow().iter() {
+                match attr.name.local.to_string().as_str() {
+                    "src" => src = Some(attr.value.to_string()),
+                    "type" => script_type = Some(attr.value.to_string()),
+                    _ => {}
                 }
             }
+
+            if tag == "script" && src.is_some() {
+                references.script_srcs.push(src.unwrap());
+            } else if tag == "script" && is_js_type(script_type.as_deref()) {
+                push_inline(node, "js", references);
+            } else if tag == "style" {
+                push_inline(node, "css", references);
+            }
         }
         _ => {}
     }
 
     for child in node.children.borrow().iter() {
-        walk(child, attrs_list);
+        walk(child, references);
+    }
+}
+
+/// Inline scripts with another type, like `application/ld+json`, are data rather than code
+fn is_js_type(script_type: Option<&str>) -> bool {
+    return match script_type {
+        None | Some("") | Some("module") | Some("text/javascript") | Some("application/javascript") => true,
+        _ => false,
+    };
+}
+
+fn push_inline(node: &Handle, asset_type: &str, references: &mut References) {
+    let mut content = String::new();
+    for child in node.children.borrow().iter() {
+        if let NodeData::Text { ref contents } = child.data {
+            content.push_str(&contents.borrow());
+        }
+    }
+    if content.trim().len() == 0 {
+        return;
     }
+    references.inline.push((asset_type.to_string(), content));
 }
@@ -0,0 +1,381 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map, Value};
+
+use crate::core::{Asset, Invalidation};
+use crate::transformation::{LoadedConfig, TransformerContext, TransformerError};
+
+/// JSX libraries looked for in package.json dependencies: name, pragma, fragment
+/// pragma and the first version with the automatic runtime
+const JSX_LIBRARIES: &[(&str, &str, Option<&str>, Option<(u64, u64)>)] = &[
+    ("react", "React.createElement", Some("React.Fragment"), Some((16, 14))),
+    ("preact", "h", Some("Fragment"), Some((10, 5))),
+    ("nervjs", "Nerv.createElement", None, None),
+    ("hyperapp", "h", None, None),
+];
+
+/// How JS and TS are compiled, inferred from the package.json and tsconfig.json
+/// (or jsconfig.json) closest to the asset, with the tsconfigs it `extends`. The tsconfig wins
+/// where both say something
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct JsConfig {
+    /// A JSX library is a dependency or the tsconfig sets `jsx`, so .js files can contain JSX
+    pub has_jsx: bool,
+    pub jsx_pragma: Option<String>,
+    pub jsx_pragma_frag: Option<String>,
+    pub automatic_jsx_runtime: bool,
+    pub jsx_import_source: Option<String>,
+    pub decorators: bool,
+    pub use_define_for_class_fields: bool,
+}
+
+impl JsConfig {
+    pub fn load(ctx: &TransformerContext, asset: &Asset) -> Result<JsConfig, TransformerError> {
+        let mut config = JsConfig::default();
+
+        let package_json = ctx.load_config(&asset.file_path, &["package.json"]);
+        if package_json.is_err() {
+            return Err(TransformerError::new(&package_json.err().unwrap()));
+        }
+        if let Some(package_json) = package_json.unwrap() {
+            config.apply_package_json(&package_json.contents);
+        }
+
+        let tsconfig = ctx.load_config(&asset.file_path, &["tsconfig.json", "jsconfig.json"]);
+        if tsconfig.is_err() {
+            return Err(TransformerError::new(&tsconfig.err().unwrap()));
+        }
+        if let Some(tsconfig) = tsconfig.unwrap() {
+            let mut compiler_options = Map::new();
+            let result = collect_compiler_options(ctx, &tsconfig, &mut compiler_options, &mut Vec::new());
+            if result.is_err() {
+                return Err(TransformerError::new(&result.err().unwrap()));
+            }
+            config.apply_compiler_options(&Value::Object(compiler_options));
+        }
+
+        return Ok(config);
+    }
+
+    fn apply_package_json(&mut self, package_json: &Value) {
+        for (name, pragma, pragma_frag, automatic_since) in JSX_LIBRARIES {
+            let Some(range) = get_dependency_range(package_json, name) else {
+                continue;
+            };
+
+            self.has_jsx = true;
+            self.jsx_pragma = Some(pragma.to_string());
+            self.jsx_pragma_frag = pragma_frag.map(String::from);
+            self.automatic_jsx_runtime = match (automatic_since, get_min_version(range)) {
+                _ if range == "latest" || range == "next" => automatic_since.is_some(),
+                (Some(since), Some(version)) => version >= *since,
+                _ => false,
+            };
+            return;
+        }
+    }
+
+    fn apply_compiler_options(&mut self, compiler_options: &Value) {
+        if let Some(pragma) = compiler_options["jsxFactory"].as_str() {
+            self.has_jsx = true;
+            self.jsx_pragma = Some(pragma.to_string());
+        }
+        if let Some(pragma_frag) = compiler_options["jsxFragmentFactory"].as_str() {
+            self.jsx_pragma_frag = Some(pragma_frag.to_string());
+        }
+        if let Some(import_source) = compiler_options["jsxImportSource"].as_str() {
+            self.has_jsx = true;
+            self.automatic_jsx_runtime = true;
+            self.jsx_import_source = Some(import_source.to_string());
+        }
+
+        match compiler_options["jsx"].as_str() {
+            Some("react-jsx") | Some("react-jsxdev") => {
+                self.has_jsx = true;
+                self.automatic_jsx_runtime = true;
+            }
+            Some("react") => {
+                self.has_jsx = true;
+                self.automatic_jsx_runtime = false;
+            }
+            Some(_) => self.has_jsx = true,
+            None => {}
+        }
+
+        if let Some(decorators) = compiler_options["experimentalDecorators"].as_bool() {
+            self.decorators = decorators;
+        }
+        self.use_define_for_class_fields = match compiler_options["useDefineForClassFields"].as_bool() {
+            Some(use_define) => use_define,
+            // TypeScript's default when targeting ES2022 and later
+            None => is_es2022_or_later(compiler_options["target"].as_str()),
+        };
+    }
+}
+
+/// Adds the compilerOptions of `tsconfig` that aren't set yet, then those of the tsconfigs
+/// it `extends`, so a config's own options win over the ones it inherits
+fn collect_compiler_options(
+    ctx: &TransformerContext,
+    tsconfig: &LoadedConfig,
+    compiler_options: &mut Map<String, Value>,
+    visited: &mut Vec<PathBuf>,
+) -> Result<(), String> {
+    visited.push(tsconfig.file_path.clone());
+    if let Some(options) = tsconfig.contents["compilerOptions"].as_object() {
+        for (name, value) in options {
+            if !compiler_options.contains_key(name) {
+                compiler_options.insert(name.clone(), value.clone());
+            }
+        }
+    }
+
+    // Later entries of an `extends` array win over earlier ones, so they are added first
+    let extends: Vec<&str> = match &tsconfig.contents["extends"] {
+        Value::String(extends) => vec![extends.as_str()],
+        Value::Array(extends) => extends.iter().rev().filter_map(|extends| extends.as_str()).collect(),
+        _ => Vec::new(),
+    };
+
+    for specifier in extends {
+        let Some(file_path) = resolve_extends(ctx, &tsconfig.file_path, specifier) else {
+            return Err(format!("Cannot find {} extended by {}", specifier, tsconfig.file_path.display()));
+        };
+        if visited.contains(&file_path) {
+            continue;
+        }
+
+        // The file can be gone since it was found
+        let Some(contents) = ctx.config_cache.read(&file_path)? else {
+            return Err(format!("Cannot find {} extended by {}", specifier, tsconfig.file_path.display()));
+        };
+        let extended = LoadedConfig {
+            file_path,
+            contents,
+        };
+        collect_compiler_options(ctx, &extended, compiler_options, visited)?;
+    }
+
+    return Ok(());
+}
+
+/// Finds the file a tsconfig `extends`, a path relative to the tsconfig or a package in the
+/// node_modules directories up to the project root. `.json` can be left out, and a package's
+/// tsconfig.json is used when it names the package alone
+fn resolve_extends(ctx: &TransformerContext, from: &Path, specifier: &str) -> Option<PathBuf> {
+    let mut dir = from.parent()?;
+    if specifier.starts_with('.') || Path::new(specifier).is_absolute() {
+        return find_tsconfig(ctx, &dir.join(specifier));
+    }
+
+    loop {
+        if let Some(file_path) = find_tsconfig(ctx, &dir.join("node_modules").join(specifier)) {
+            return Some(file_path);
+        }
+        if dir == ctx.resolver.options.project_root {
+            return None;
+        }
+        dir = dir.parent()?;
+    }
+}
+
+fn find_tsconfig(ctx: &TransformerContext, path: &Path) -> Option<PathBuf> {
+    let mut with_extension = path.as_os_str().to_owned();
+    with_extension.push(".json");
+
+    for file_path in [path.to_path_buf(), PathBuf::from(with_extension), path.join("tsconfig.json")] {
+        if file_path.is_file() {
+            ctx.add_invalidation(Invalidation::FileChange(file_path.clone()));
+            return Some(file_path);
+        }
+        ctx.add_invalidation(Invalidation::FileCreate(file_path));
+    }
+    return None;
+}
+
+fn get_dependency_range<'a>(package_json: &'a Value, name: &str) -> Option<&'a str> {
+    return ["dependencies", "devDependencies", "peerDependencies"]
+        .iter()
+        .find_map(|field| package_json[field][name].as_str());
+}
+
+/// Major and minor of the lowest version a range like `^17.0.2` or `>= 16.14` allows,
+/// None for ranges that aren't versions, like `*` or `file:../react`
+fn get_min_version(range: &str) -> Option<(u64, u64)> {
+    let version = range.trim_start_matches(|c: char| "^~>=v ".contains(c));
+    let mut parts = version
+        .split(|c: char| !c.is_ascii_digit() && c != '.')
+        .next()?
+        .split('.');
+
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next().and_then(|minor| minor.parse().ok()).unwrap_or(0);
+    return Some((major, minor));
+}
+
+fn is_es2022_or_later(target: Option<&str>) -> bool {
+    let Some(target) = target else {
+        return false;
+    };
+    let target = target.to_lowercase();
+    if target == "esnext" {
+        return true;
+    }
+    return target
+        .strip_prefix("es")
+        .and_then(|year| year.parse::<u32>().ok())
+        .map_or(false, |year| year >= 2022);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use crate::core::{AssetGraph, AssetKey, BuildMode, Environment, EnvironmentContext};
+    use crate::platform::LogLevel;
+    use crate::resolver::{Resolver, ResolverOptions};
+    use crate::transformation::ConfigCache;
+
+    use super::*;
+
+    #[test]
+    fn test_react_version_picks_runtime() {
+        let runtime = |range: &str| {
+            let mut config = JsConfig::default();
+            config.apply_package_json(&json!({ "dependencies": { "react": range } }));
+            return config.automatic_jsx_runtime;
+        };
+
+        assert!(runtime("^18.2.0"));
+        assert!(runtime("~16.14.0"));
+        assert!(runtime(">= 17"));
+        assert!(runtime("latest"));
+        assert!(!runtime("^16.8.0"));
+        assert!(!runtime("file:../react"));
+    }
+
+    #[test]
+    fn test_preact() {
+        let mut config = JsConfig::default();
+        config.apply_package_json(&json!({ "devDependencies": { "preact": "10.4.0" } }));
+
+        assert!(config.has_jsx);
+        assert!(!config.automatic_jsx_runtime);
+        assert_eq!(config.jsx_pragma.as_deref(), Some("h"));
+        assert_eq!(config.jsx_pragma_frag.as_deref(), Some("Fragment"));
+    }
+
+    #[test]
+    fn test_compiler_options_win() {
+        let mut config = JsConfig::default();
+        config.apply_package_json(&json!({ "dependencies": { "react": "^18.0.0" } }));
+        config.apply_compiler_options(&json!({
+            "jsx": "react",
+            "jsxFactory": "h",
+            "experimentalDecorators": true,
+            "target": "ES2022",
+        }));
+
+        assert!(!config.automatic_jsx_runtime);
+        assert_eq!(config.jsx_pragma.as_deref(), Some("h"));
+        assert_eq!(config.jsx_pragma_frag.as_deref(), Some("React.Fragment"));
+        assert!(config.decorators);
+        assert!(config.use_define_for_class_fields);
+    }
+
+    #[test]
+    fn test_jsx_import_source() {
+        let mut config = JsConfig::default();
+        config.apply_compiler_options(&json!({
+            "jsxImportSource": "@emotion/react",
+            "useDefineForClassFields": false,
+            "target": "esnext",
+        }));
+
+        assert!(config.has_jsx);
+        assert!(config.automatic_jsx_runtime);
+        assert_eq!(config.jsx_import_source.as_deref(), Some("@emotion/react"));
+        assert!(!config.use_define_for_class_fields);
+    }
+
+    fn extends_fixture() -> PathBuf {
+        return Path::new(env!("CARGO_MANIFEST_DIR")).join("../fixtures/tsconfig-extends").canonicalize().unwrap();
+    }
+
+    fn load(file_path: PathBuf) -> (Result<JsConfig, TransformerError>, Vec<Invalidation>) {
+        return load_with(file_path, extends_fixture(), ConfigCache::new());
+    }
+
+    fn load_with(
+        file_path: PathBuf,
+        project_root: PathBuf,
+        config_cache: ConfigCache,
+    ) -> (Result<JsConfig, TransformerError>, Vec<Invalidation>) {
+        let env = Environment::new(EnvironmentContext::Browser, BuildMode::Development);
+        let key = AssetKey {
+            file_path,
+            query: None,
+            pipeline: None,
+            unique_key: None,
+            env: env.clone(),
+        };
+        let ctx = TransformerContext::new(
+            0,
+            env,
+            AssetGraph::new(),
+            LogLevel::Info,
+            Arc::new(Resolver::new(ResolverOptions::with_project_root(project_root))),
+            Arc::new(config_cache),
+        );
+
+        let config = JsConfig::load(&ctx, &Asset::new(&key));
+        return (config, ctx.take_invalidations());
+    }
+
+    #[test]
+    fn test_tsconfig_extends() {
+        let project_root = extends_fixture();
+        let (config, invalidations) = load(project_root.join("index.ts"));
+        let config = config.unwrap();
+
+        // jsx from configs/base.json, over the package's, and its own jsxFactory over the base's
+        assert!(!config.automatic_jsx_runtime);
+        assert_eq!(config.jsx_pragma.as_deref(), Some("h"));
+        // Only set in the package configs/base.json extends
+        assert!(config.decorators);
+
+        assert!(invalidations.contains(&Invalidation::FileChange(project_root.join("configs/base.json"))));
+        assert!(invalidations.contains(&Invalidation::FileChange(project_root.join("node_modules/@company/tsconfig/tsconfig.json"))));
+    }
+
+    #[test]
+    fn test_missing_tsconfig_extends() {
+        let project_root = extends_fixture();
+        let (config, _) = load(project_root.join("broken/index.ts"));
+        assert_eq!(
+            config.err().unwrap().message,
+            format!("Cannot find ./missing extended by {}", project_root.join("broken/tsconfig.json").display()),
+        );
+    }
+
+    #[test]
+    fn test_extended_tsconfig_gone_when_read() {
+        let project_root = std::env::temp_dir().join(format!("hypersonic-extends-{}", std::process::id()));
+        std::fs::create_dir_all(&project_root).unwrap();
+        std::fs::write(project_root.join("tsconfig.json"), r#"{ "extends": "./base.json" }"#).unwrap();
+
+        // The cache still remembers base.json as missing when it is found on disk
+        let config_cache = ConfigCache::new();
+        assert!(config_cache.read(&project_root.join("base.json")).unwrap().is_none());
+        std::fs::write(project_root.join("base.json"), "{}").unwrap();
+
+        let (config, _) = load_with(project_root.join("index.ts"), project_root.clone(), config_cache);
+        let _ = std::fs::remove_dir_all(&project_root);
+        assert_eq!(
+            config.err().unwrap().message,
+            format!("Cannot find ./base.json extended by {}", project_root.join("tsconfig.json").display()),
+        );
+    }
+}
@@ -0,0 +1,20 @@
+use std::collections::HashSet;
+
+use parcel_transformer_js::{CollectResult, HoistResult};
+
+/// Stored in `Asset::meta` by `DefaultJSTransformer`, for bundling, tree shaking
+/// and invalidating the asset when the environment variables it read change
+#[derive(Debug, Default)]
+pub struct JsMeta {
+    /// Set when scope hoisting
+    pub hoist_result: Option<HoistResult>,
+    /// Set when collecting symbols without scope hoisting
+    pub symbol_result: Option<CollectResult>,
+    /// Names of the `process.env` variables that were inlined
+    pub used_env: HashSet<String>,
+    pub needs_esm_helpers: bool,
+    pub has_node_replacements: bool,
+    pub is_constant_module: bool,
+    /// Removed from the code, eg `#!/usr/bin/env node`
+    pub shebang: Option<String>,
+}
@@ -1,3 +1,7 @@
+mod js_config;
+mod js_meta;
 mod transformer;
 
+pub use crate::default_plugins::transformer_js::js_config::*;
+pub use crate::default_plugins::transformer_js::js_meta::*;
 pub use crate::default_plugins::transformer_js::transformer::*;
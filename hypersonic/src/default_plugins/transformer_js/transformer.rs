@@ -1,10 +1,13 @@
 
-use parcel_transformer_js::{Config, transform};
+use parcel_transformer_js::{Config, DependencyDescriptor, Diagnostic, DiagnosticSeverity, transform};
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::str;
 
-use crate::core::Asset;
-use crate::resolver::resolve;
-use crate::transformation::{Transformer, TransformerContext, TransformerResult};
+use crate::core::{Asset, CodeHighlight, Content, Dependency, DependencyKind, Environment, OutputFormat, Severity, SourceLocation, SourceMap, SourceType, split_pipeline};
+use crate::transformation::{Replacement, Transformer, TransformerContext, TransformerError, TransformerResult};
+
+use super::{JsConfig, JsMeta};
 
 pub struct DefaultJSTransformer {
     is_jsx: bool,
@@ -39,72 +42,264 @@ impl Transformer for DefaultJSTransformer {
         ctx: &TransformerContext,
         asset: &mut Asset,
     ) -> TransformerResult {
-        let log_level = ctx.get_log_level();
+        let js_config = JsConfig::load(ctx, asset);
+        if js_config.is_err() {
+            return TransformerResult::Err(js_config.err().unwrap());
+        }
+        let js_config = js_config.unwrap();
 
         let mut config = Config::new();
         config.code = asset.content.as_bytes().to_vec();
         config.filename = asset.file_path.to_str().unwrap().to_string();
-        config.is_jsx = self.is_jsx;
+        // JSX in .ts files would be ambiguous with type assertions, so only .js files opt in
+        config.is_jsx = self.is_jsx || (js_config.has_jsx && !self.is_type_script);
         config.is_type_script = self.is_type_script;
+        config.jsx_pragma = js_config.jsx_pragma;
+        config.jsx_pragma_frag = js_config.jsx_pragma_frag;
+        config.automatic_jsx_runtime = js_config.automatic_jsx_runtime;
+        config.jsx_import_source = js_config.jsx_import_source;
+        config.decorators = js_config.decorators;
+        config.use_define_for_class_fields = js_config.use_define_for_class_fields;
+
+        let env = ctx.get_env();
+        config.is_browser = env.is_browser();
+        config.is_worker = env.is_worker();
+        config.is_development = env.is_development();
+        config.source_maps = env.source_maps;
+        config.is_esm_output = env.output_format == OutputFormat::EsModule;
+        config.source_type = match env.source_type {
+            SourceType::Module => parcel_transformer_js::SourceType::Module,
+            SourceType::Script => parcel_transformer_js::SourceType::Script,
+        };
+        if let Some(targets) = &env.targets {
+            config.targets = Some(HashMap::from_iter(targets.clone()));
+        }
 
         let transformation_res = transform(config);
         if transformation_res.is_err() {
-            return TransformerResult::Break;
+            return TransformerResult::Err(TransformerError::new(&format!(
+                "Unable to transform: {}",
+                transformation_res.err().unwrap(),
+            )));
         }
 
         let transformation = transformation_res.unwrap();
+
+        // Parse errors come back as diagnostics with empty code rather than as an Err
+        if let Some(diagnostics) = &transformation.diagnostics {
+            let is_dependency = asset.file_path.components().any(|c| c.as_os_str() == "node_modules");
+            let mut errors = Vec::new();
+
+            for diagnostic in diagnostics {
+                let severity = match diagnostic.severity {
+                    DiagnosticSeverity::Error => Severity::Error,
+                    DiagnosticSeverity::Warning => Severity::Warning,
+                    // Problems in dependencies are for their authors to fix
+                    DiagnosticSeverity::SourceError if is_dependency => Severity::Warning,
+                    DiagnosticSeverity::SourceError => Severity::Error,
+                };
+                match severity {
+                    Severity::Error => errors.push(to_transformer_error(diagnostic)),
+                    Severity::Warning => ctx.add_warning(to_transformer_error(diagnostic)),
+                }
+            }
+
+            if errors.len() != 0 {
+                let first = errors.remove(0);
+                for error in errors {
+                    ctx.add_diagnostic(Severity::Error, error);
+                }
+                return TransformerResult::Err(first);
+            }
+        }
         let code_res = str::from_utf8(transformation.code.as_slice());
         if code_res.is_err() {
-            return TransformerResult::Break;
+            return TransformerResult::Err(TransformerError::new("Transformed code is not valid UTF-8"));
         }
+        let code = code_res.unwrap().to_string();
 
-        asset.content = code_res.unwrap().to_string();
-
-        'outer: for descriptor in transformation.dependencies {
-            let result = resolve(
-                descriptor.specifier.as_str(),
+        for descriptor in transformation.dependencies {
+            let (pipeline, specifier) = split_pipeline(descriptor.specifier.as_str());
+            let result = ctx.resolve(
+                specifier,
                 &asset.file_path,
+                &to_dependency_kind(&descriptor.kind),
             );
 
-            if result.is_ok() {
-                ctx.add_dependency(result.unwrap());
-                continue;
+            if result.is_err() {
+                let mut error = TransformerError::new(&format!(
+                    "Unable to resolve \"{}\": {}",
+                    descriptor.specifier,
+                    result.err().unwrap(),
+                ));
+                error.loc = Some(to_source_location(&descriptor.loc));
+                return TransformerResult::Err(error);
             }
-            
-            let mut log = String::new();
 
-            if log_level.is_verbose() {
-                log.push_str(&format!("TRYING: {}\n", descriptor.specifier.as_str()))
-            }
+            let mut dependency = to_dependency(&descriptor, PathBuf::new(), env);
+            dependency.pipeline = pipeline;
+            ctx.add_resolved_dependency(dependency, result.unwrap());
+        }
 
-            for try_this in [
-                ".js", ".jsx", ".ts", ".tsx",
-                "/index.js", "/index.jsx", "/index.ts", "/index.tsx",
-                "/src/index.js", "/src/index.jsx", "/src/index.ts", "/src/index.tsx",
-            ] {
-                let spec = format!("{}{}", descriptor.specifier.as_str(), try_this);
-                if log_level.is_verbose() {
-                    log.push_str(&format!("  {}\n", spec));
-                }
+        asset.meta.insert(JsMeta {
+            hoist_result: transformation.hoist_result,
+            symbol_result: transformation.symbol_result,
+            used_env: transformation.used_env.iter().map(|name| name.to_string()).collect(),
+            needs_esm_helpers: transformation.needs_esm_helpers,
+            has_node_replacements: transformation.has_node_replacements,
+            is_constant_module: transformation.is_constant_module,
+            shebang: transformation.shebang,
+        });
 
-                let result = resolve(
-                    &spec,
-                    &asset.file_path,
-                );
-                if result.is_ok() {
-                    if log_level.is_verbose() {
-                        log.push_str(&format!("FOUND: {}", spec));
-                        println!("{}", log);
-                    }
-                    ctx.add_dependency(result.unwrap());
-                    break 'outer;
-                }
+        let mut replacement = Replacement::new(Content::from(code));
+        if let Some(map) = &transformation.map {
+            let project_root = ctx.resolver.options.project_root.to_string_lossy();
+            let map = SourceMap::from_json(&project_root, map);
+            if map.is_err() {
+                return TransformerResult::Err(TransformerError::new(&format!(
+                    "Invalid source map: {}",
+                    map.err().unwrap(),
+                )));
             }
-            
-            println!("Resolve Error, {}", descriptor.specifier);
-            return TransformerResult::Err(String::from(""));
+            replacement.map = Some(map.unwrap());
         }
 
-        return TransformerResult::Continue;
+        return TransformerResult::Replace(replacement);
+    }
+}
+
+fn to_dependency_kind(kind: &parcel_transformer_js::DependencyKind) -> DependencyKind {
+    return match kind {
+        parcel_transformer_js::DependencyKind::Import => DependencyKind::Import,
+        parcel_transformer_js::DependencyKind::Export => DependencyKind::Export,
+        parcel_transformer_js::DependencyKind::DynamicImport => DependencyKind::DynamicImport,
+        parcel_transformer_js::DependencyKind::Require => DependencyKind::Require,
+        parcel_transformer_js::DependencyKind::WebWorker => DependencyKind::WebWorker,
+        parcel_transformer_js::DependencyKind::ServiceWorker => DependencyKind::ServiceWorker,
+        parcel_transformer_js::DependencyKind::Worklet => DependencyKind::Worklet,
+        parcel_transformer_js::DependencyKind::Url => DependencyKind::Url,
+        parcel_transformer_js::DependencyKind::File => DependencyKind::File,
+    };
+}
+
+fn to_dependency(descriptor: &DependencyDescriptor, file_path: PathBuf, env: &Environment) -> Dependency {
+    let kind = to_dependency_kind(&descriptor.kind);
+
+    let mut dependency_env = env.for_dependency(&kind);
+    if let Some(source_type) = &descriptor.source_type {
+        dependency_env.source_type = match source_type {
+            parcel_transformer_js::SourceType::Module => SourceType::Module,
+            parcel_transformer_js::SourceType::Script => SourceType::Script,
+        };
+    }
+
+    let mut dependency = Dependency::new(descriptor.specifier.as_ref(), kind, file_path);
+    dependency.loc = Some(to_source_location(&descriptor.loc));
+    dependency.env = Some(dependency_env);
+    return dependency;
+}
+
+fn to_source_location(loc: &parcel_transformer_js::SourceLocation) -> SourceLocation {
+    return SourceLocation {
+        start_line: loc.start_line,
+        start_col: loc.start_col,
+        end_line: loc.end_line,
+        end_col: loc.end_col,
+    };
+}
+
+fn to_transformer_error(diagnostic: &Diagnostic) -> TransformerError {
+    let mut error = TransformerError::new(&diagnostic.message);
+    error.highlights = diagnostic.code_highlights
+        .iter()
+        .flatten()
+        .map(|highlight| CodeHighlight {
+            message: highlight.message.clone(),
+            loc: to_source_location(&highlight.loc),
+        })
+        .collect();
+    error.loc = error.highlights.first().map(|highlight| highlight.loc.clone());
+    error.hints = diagnostic.hints.clone().unwrap_or_default();
+    error.documentation_url = diagnostic.documentation_url.clone();
+    return error;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use crate::core::{AssetGraph, AssetKey, BuildMode, EnvironmentContext};
+    use crate::platform::LogLevel;
+    use crate::resolver::{Resolver, ResolverOptions};
+    use crate::transformation::ConfigCache;
+
+    use super::*;
+
+    #[test]
+    fn test_node_builtins_are_external_dependencies() {
+        let project_root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../fixtures/builtins").canonicalize().unwrap();
+        let env = Environment::new(EnvironmentContext::Node, BuildMode::Development);
+        let key = AssetKey {
+            file_path: project_root.join("index.js"),
+            query: None,
+            pipeline: None,
+            unique_key: None,
+            env: env.clone(),
+        };
+        let mut asset = Asset::new(&key);
+        asset.content = Content::from(String::from("import fs from 'fs';\nconsole.log(fs);"));
+
+        let ctx = TransformerContext::new(
+            0,
+            env,
+            AssetGraph::new(),
+            LogLevel::Info,
+            Arc::new(Resolver::new(ResolverOptions::with_project_root(project_root))),
+            Arc::new(ConfigCache::new()),
+        );
+        let result = DefaultJSTransformer::new(false, false).transform(&ctx, &mut asset);
+        assert_eq!(result.get_name(), "Replace");
+
+        let dependencies = ctx.take_dependencies();
+        assert_eq!(dependencies.len(), 1);
+        assert_eq!(dependencies[0].specifier, "fs");
+        assert!(dependencies[0].is_external);
+        assert_eq!(ctx.take_inline_assets().len(), 0);
+    }
+
+    #[test]
+    fn test_empty_modules_keep_the_import() {
+        let project_root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../fixtures/builtins").canonicalize().unwrap();
+        let env = Environment::new(EnvironmentContext::Browser, BuildMode::Development);
+        let key = AssetKey {
+            file_path: project_root.join("index.js"),
+            query: None,
+            pipeline: None,
+            unique_key: None,
+            env: env.clone(),
+        };
+        let mut asset = Asset::new(&key);
+        asset.content = Content::from(String::from("import fs from 'fs';\nconsole.log(fs);"));
+
+        let ctx = TransformerContext::new(
+            0,
+            env,
+            AssetGraph::new(),
+            LogLevel::Info,
+            Arc::new(Resolver::new(ResolverOptions::with_project_root(project_root))),
+            Arc::new(ConfigCache::new()),
+        );
+        let result = DefaultJSTransformer::new(false, false).transform(&ctx, &mut asset);
+        assert_eq!(result.get_name(), "Replace");
+
+        let inline_assets = ctx.take_inline_assets();
+        assert_eq!(inline_assets.len(), 1);
+        let (dependency, inline_asset) = &inline_assets[0];
+        assert_eq!(dependency.specifier, "fs");
+        assert_eq!(dependency.file_path, PathBuf::from("empty:fs"));
+        assert_eq!(dependency.unique_key.as_deref(), Some("empty:fs"));
+        assert_eq!(dependency.loc.as_ref().unwrap().start_line, 1);
+        assert_eq!(inline_asset.content, "");
     }
 }
@@ -0,0 +1,12 @@
+pub mod bundler;
+pub mod config;
+pub mod core;
+pub mod default_plugins;
+pub mod platform;
+pub mod plugins;
+pub mod resolver;
+pub mod scheduler;
+pub mod transformation;
+pub mod utils;
+
+pub use crate::bundler::*;
@@ -1,63 +1,54 @@
-mod platform;
-mod core;
-mod resolver;
-mod transformation;
-mod default_plugins;
-mod utils;
+use hypersonic::Bundler;
+use hypersonic::config::HypersonicConfig;
+use hypersonic::default_plugins::default_registry;
+use hypersonic::platform::{Args, CliReporter};
+use hypersonic::resolver::ResolverOptions;
 
-use std::sync::Arc;
+fn main() {
+    let args = Args::new();
 
-use dashmap::DashMap;
+    let mut bundler = Bundler::new()
+        .entries(args.entries.clone())
+        .threads(args.threads)
+        .scheduler(args.scheduler.clone())
+        .mode(args.mode.clone())
+        .log_level(args.log_level.clone())
+        .profiling(args.profiling)
+        .reporter(Box::new(CliReporter::new(args.log_level.clone(), args.profiling)));
+
+    let mut source_maps = args.source_maps;
+
+    if let Some(config_path) = &args.config {
+        let mut registry = default_registry();
+        let config = HypersonicConfig::load(config_path)
+            .and_then(|config| config.register_plugins(&mut registry).map(|_| config))
+            .and_then(|config| config.to_transformers(&registry).map(|transformers| (config, transformers)));
+        if let Err(error) = config {
+            println!("{}", error);
+            std::process::exit(1);
+        }
+        let (config, transformers) = config.unwrap();
+        bundler = bundler.transformers(transformers);
+        source_maps = source_maps.or(config.source_maps(&args.mode));
 
-use crate::core::Asset;
-use crate::platform::Args;
-use crate::transformation::transform;
-use crate::utils::{StandardProfiler, StaticContainer};
+        let mut resolver = ResolverOptions::new();
+        for (builtin, polyfill) in config.polyfills {
+            resolver.polyfills.insert(builtin, polyfill);
+        }
+        bundler = bundler.resolver_options(resolver);
 
+        if let Some(targets) = config.targets {
+            bundler = bundler.targets(targets);
+        }
+    }
 
-fn main() {
-    let args = Args::new();
+    if let Some(source_maps) = source_maps {
+        bundler = bundler.source_maps(source_maps);
+    }
 
-    println!("ENTRY:     {:?}", args.entry);
-    println!("LOGGING:   {:?}", args.log_level);
-    println!("PROFILING: {}", args.profiling);
-    println!("THREADS:   {}", args.threads);
-    println!("");
-
-    let assets = StaticContainer::<Asset>::new(10_000_000);
-    let assets_index = Arc::new(DashMap::<String, usize>::new());
-    let profiler = StandardProfiler::new();
-
-    let profiler_end = profiler.start();
-
-    transform(
-        &args,
-        assets.clone(),
-        assets_index.clone(),
-        &profiler,
-    );
-
-    profiler_end("build-time-total");
-
-    println!("Performance Breakdown:");
-    println!("  Total Time:      {:.5} s (total)", profiler.get_seconds("build-time-total"));
-    println!("  Total Assets:    {}", assets.len());
-
-    if args.profiling {
-        println!("  Transformation:  {:.5} s (total)", profiler.get_seconds("Transformation"));
-        println!("    CreateAsset:   {:.5} ms (average)", profiler.get_milli("CreateAsset"));
-        println!("    ReadContents:  {:.5} ms (average)", profiler.get_milli("ReadContents"));
-        println!("    AssignPattern: {:.5} ms (average)", profiler.get_milli("TransformAssignPattern"));
-        println!("    Transformers:");
-
-        let mut profiles: Vec<String> = profiler.get_profiles().clone();
-        profiles.sort();
-        for profile in  profiles {
-            if profile.starts_with("TransformContents - ") {
-                let title = profile.replace("TransformContents - ", "");
-                let perf = profiler.get_milli(&profile);
-                println!("      {}: {:.5} ms (average)", title, perf);
-            }
-        }
+    let result = bundler.build();
+
+    if result.is_err() {
+        std::process::exit(1);
     }
 }
@@ -1,13 +1,21 @@
-use std::{path::{PathBuf, Path}, env};
+use std::{path::PathBuf, env};
 
-use super::LogLevel;
+use crate::config::CONFIG_FILE_NAME;
+use crate::core::BuildMode;
+
+use super::{LogLevel, SchedulerKind, resolve_entries};
 
 #[derive(Clone, Debug)]
 pub struct Args {
-  pub entry: PathBuf,
+  pub entries: Vec<PathBuf>,
   pub threads: usize,
+  pub scheduler: SchedulerKind,
+  pub mode: BuildMode,
   pub log_level: LogLevel,
   pub profiling: bool,
+  /// None leaves it to the config, then the mode
+  pub source_maps: Option<bool>,
+  pub config: Option<PathBuf>,
 }
 
 impl Args {
@@ -20,21 +28,28 @@ impl Args {
     }
 
     return Args{
-      entry: get_entry(),
+      entries: get_entries(),
       threads: get_threads(),
+      scheduler: get_scheduler(),
+      mode: get_mode(),
       log_level,
       profiling,
+      source_maps: get_source_maps(),
+      config: get_config(),
     };
   }
 }
 
-fn get_entry() -> PathBuf {
-  let filepath_str = std::env::args().nth(1).expect("No filepath given");
-  let filepath = Path::new(&filepath_str);
-  if filepath.is_absolute() {
-    return filepath.to_owned();
+fn get_entries() -> Vec<PathBuf> {
+  let inputs: Vec<String> = std::env::args().skip(1).collect();
+  if inputs.len() == 0 {
+    panic!("No filepath given");
+  }
+  let entries = resolve_entries(&inputs, &env::current_dir().unwrap());
+  if entries.is_err() {
+    panic!("{}", entries.err().unwrap());
   }
-  return env::current_dir().unwrap().as_path().join(filepath);
+  return entries.unwrap();
 }
 
 fn parse_usize(str: &str) -> Result<usize, ()> {
@@ -62,6 +77,61 @@ fn get_threads() -> usize {
   return threads;
 }
 
+fn get_scheduler() -> SchedulerKind {
+  let scheduler_res = env::var("HS_SCHEDULER");
+  if scheduler_res.is_err() {
+    return SchedulerKind::WorkStealing;
+  }
+  let scheduler = scheduler_res.unwrap().parse::<SchedulerKind>();
+  if scheduler.is_err() {
+    panic!("Incorrect scheduler supplied\n\tTry broadcast, work-stealing, rayon, single-threaded");
+  }
+  return scheduler.unwrap();
+}
+
+fn get_mode() -> BuildMode {
+  let mode_res = env::var("HS_MODE");
+  if mode_res.is_err() {
+    return BuildMode::Development;
+  }
+  let mode = mode_res.unwrap();
+  if mode == "development" {
+    return BuildMode::Development;
+  }
+  if mode == "production" {
+    return BuildMode::Production;
+  }
+  panic!("Incorrect mode supplied\n\tTry development, production");
+}
+
+fn get_source_maps() -> Option<bool> {
+  let source_maps_res = env::var("HS_SOURCE_MAPS");
+  if source_maps_res.is_err() {
+    return None;
+  }
+  let source_maps = source_maps_res.unwrap();
+  if source_maps == "true" {
+    return Some(true);
+  }
+  if source_maps == "false" {
+    return Some(false);
+  }
+  panic!("Incorrect source maps option supplied\n\tTry true, false");
+}
+
+/// HS_CONFIG, otherwise a .hypersonicrc in the working directory if there is one
+fn get_config() -> Option<PathBuf> {
+  let config_res = env::var("HS_CONFIG");
+  if config_res.is_ok() {
+    return Some(PathBuf::from(config_res.unwrap()));
+  }
+  let config = env::current_dir().unwrap().join(CONFIG_FILE_NAME);
+  if !config.is_file() {
+    return None;
+  }
+  return Some(config);
+}
+
 fn get_log_level() -> LogLevel {
   let log_level_res = env::var("HS_LOG_LEVEL");
   if log_level_res.is_err() {
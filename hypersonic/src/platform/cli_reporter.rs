@@ -0,0 +1,102 @@
+use crate::bundler::{BuildOptions, BuildResult, Reporter, ReporterEvent};
+
+use super::{LogLevel, render_diagnostic, supports_color};
+
+/// Prints build progress and the performance breakdown to stdout
+pub struct CliReporter {
+  log_level: LogLevel,
+  profiling: bool,
+  color: bool,
+}
+
+impl CliReporter {
+  pub fn new(log_level: LogLevel, profiling: bool) -> Self {
+    return CliReporter {
+      log_level,
+      profiling,
+      color: supports_color(),
+    };
+  }
+
+  fn report_start(&self, options: &BuildOptions) {
+    for entry in &options.entries {
+      println!("ENTRY:     {:?}", entry);
+    }
+    println!("LOGGING:   {:?}", options.log_level);
+    println!("PROFILING: {}", options.profiling);
+    println!("THREADS:   {}", options.threads);
+    println!("SCHEDULER: {:?}", options.scheduler);
+    println!("MODE:      {:?}", options.mode);
+    println!("SOURCEMAPS:{}", options.source_maps_enabled());
+    println!("");
+  }
+
+  fn report_end(&self, result: &BuildResult) {
+    if self.log_level.is_verbose() {
+      println!("");
+    }
+
+    let profiler = &result.timings;
+
+    println!("Performance Breakdown:");
+    println!("  Total Time:      {:.5} s (total)", profiler.get_seconds("build-time-total"));
+    println!("  Total Assets:    {}", result.assets.len());
+    println!("  Total Deps:      {}", result.graph.len());
+
+    if self.profiling {
+      println!("  Transformation:  {:.5} s (total)", profiler.get_seconds("Transformation"));
+      println!("    CreateAsset:   {:.5} ms (average)", profiler.get_milli("CreateAsset"));
+      println!("    ReadContents:  {:.5} ms (average)", profiler.get_milli("ReadContents"));
+      println!("    AssignPattern: {:.5} ms (average)", profiler.get_milli("TransformAssignPattern"));
+      println!("    Transformers:");
+
+      let mut profiles: Vec<String> = profiler.get_profiles().clone();
+      profiles.sort();
+      for profile in  profiles {
+        if profile.starts_with("TransformContents - ") {
+          let title = profile.replace("TransformContents - ", "");
+          let perf = profiler.get_milli(&profile);
+          println!("      {}: {:.5} ms (average)", title, perf);
+        }
+      }
+    }
+
+    for warning in result.warnings() {
+      println!("");
+      print!("{}", render_diagnostic(warning, self.color));
+    }
+
+    if result.is_err() {
+      println!("");
+      println!("Build failed with {} error(s):", result.errors().count());
+      for error in result.errors() {
+        println!("");
+        print!("{}", render_diagnostic(error, self.color));
+      }
+    }
+  }
+}
+
+impl Reporter for CliReporter {
+  fn report(&self, event: &ReporterEvent) {
+    match event {
+      ReporterEvent::BuildStart(options) => self.report_start(options),
+      ReporterEvent::Action(t, action) => {
+        if self.log_level.is_verbose() {
+          println!("T{}: {:?}", t, action);
+        }
+      }
+      ReporterEvent::TransformersMatched(id, pattern_match) => {
+        if self.log_level.is_verbose() {
+          println!("    Asset {}: {}", id, pattern_match);
+        }
+      }
+      ReporterEvent::TransformerResult(id, transformer, result) => {
+        if self.log_level.is_verbose() {
+          println!("    Asset {}: {} returned {}", id, transformer, result);
+        }
+      }
+      ReporterEvent::BuildEnd(result) => self.report_end(result),
+    }
+  }
+}
@@ -0,0 +1,191 @@
+use std::env;
+use std::io::{self, IsTerminal};
+
+use crate::core::{BuildError, CodeHighlight, Severity};
+
+/// Lines shown above and below the highlighted ones
+const CONTEXT_LINES: usize = 2;
+
+const RED: &str = "31";
+const YELLOW: &str = "33";
+const CYAN: &str = "36";
+const GRAY: &str = "90";
+const BOLD: &str = "1";
+
+/// Colors are used when printing to a terminal, unless NO_COLOR is set
+pub fn supports_color() -> bool {
+  return io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none();
+}
+
+/// A diagnostic with its location, a code frame of the lines it points at, hints and documentation link
+pub fn render_diagnostic(diagnostic: &BuildError, color: bool) -> String {
+  let (label, label_color) = match diagnostic.severity {
+    Severity::Error => ("error", RED),
+    Severity::Warning => ("warning", YELLOW),
+  };
+  let mut output = format!(
+    "{}: {}\n",
+    paint(&paint(label, label_color, color), BOLD, color),
+    paint(&diagnostic.message, BOLD, color),
+  );
+
+  let highlights = diagnostic.get_highlights();
+  output.push_str(&format!("  at {}", diagnostic.file_path.to_string_lossy()));
+  if let Some(first) = highlights.first() {
+    output.push_str(&format!(":{}:{}", first.loc.start_line, first.loc.start_col));
+  }
+  match &diagnostic.transformer {
+    Some(transformer) => output.push_str(&format!(" ({})\n", transformer)),
+    None => output.push_str(&format!(" ({:?})\n", diagnostic.stage)),
+  }
+
+  if let Some(code) = &diagnostic.code {
+    if highlights.len() != 0 {
+      output.push('\n');
+      output.push_str(&render_code_frame(code, &highlights, label_color, color));
+    }
+  }
+
+  if diagnostic.hints.len() != 0 || diagnostic.documentation_url.is_some() {
+    output.push('\n');
+  }
+  for hint in &diagnostic.hints {
+    output.push_str(&format!("  {} {}\n", paint("hint:", CYAN, color), hint));
+  }
+  if let Some(url) = &diagnostic.documentation_url {
+    output.push_str(&format!("  {} {}\n", paint("Learn more:", CYAN, color), url));
+  }
+
+  return output;
+}
+
+/// The highlighted lines of `code` and a few around them, with each range
+/// underlined and followed by its message
+pub fn render_code_frame(code: &str, highlights: &[CodeHighlight], highlight_color: &str, color: bool) -> String {
+  let lines: Vec<&str> = code.lines().collect();
+  if lines.len() == 0 || highlights.len() == 0 {
+    return String::new();
+  }
+
+  let first_line = highlights.iter().map(|h| h.loc.start_line).min().unwrap().max(1);
+  let last_line = highlights.iter().map(|h| h.loc.end_line.max(h.loc.start_line)).max().unwrap();
+  let start = first_line.saturating_sub(CONTEXT_LINES).max(1);
+  let end = (last_line + CONTEXT_LINES).min(lines.len());
+  if start > end {
+    return String::new();
+  }
+
+  let gutter_width = end.to_string().len();
+  let empty_gutter = format!("  {} {} |", " ", " ".repeat(gutter_width));
+
+  let mut output = String::new();
+  for line_number in start..=end {
+    let line = lines[line_number - 1];
+    let underlines: Vec<(usize, usize, &Option<String>)> = highlights
+      .iter()
+      .filter_map(|h| get_underline(h, line_number, line).map(|(from, to)| (from, to, &h.message)))
+      .collect();
+
+    let marker = if underlines.len() != 0 { paint(">", highlight_color, color) } else { String::from(" ") };
+    let gutter = paint(&format!("{:>width$} |", line_number, width = gutter_width), GRAY, color);
+    output.push_str(&format!("  {} {} {}\n", marker, gutter, line));
+
+    for (from, to, message) in underlines {
+      // Tabs are kept so the underline lines up with the code above it
+      let padding: String = line.chars().take(from).map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+      let mut underline = "^".repeat(to - from);
+      if let Some(message) = message {
+        underline.push_str(&format!(" {}", message));
+      }
+      output.push_str(&format!(
+        "{} {}{}\n",
+        paint(&empty_gutter, GRAY, color),
+        padding,
+        paint(&underline, highlight_color, color),
+      ));
+    }
+  }
+  return output;
+}
+
+/// 0-based character range of a highlight on one of its lines, at least one character wide
+fn get_underline(highlight: &CodeHighlight, line_number: usize, line: &str) -> Option<(usize, usize)> {
+  let loc = &highlight.loc;
+  let end_line = loc.end_line.max(loc.start_line);
+  if line_number < loc.start_line || line_number > end_line {
+    return None;
+  }
+
+  let length = line.chars().count();
+  let from = if line_number == loc.start_line { loc.start_col.saturating_sub(1) } else { 0 };
+  let to = if line_number == end_line { loc.end_col.saturating_sub(1) } else { length };
+  let from = from.min(length);
+  let to = to.min(length).max(from + 1);
+  return Some((from, to));
+}
+
+fn paint(text: &str, code: &str, color: bool) -> String {
+  if !color {
+    return text.to_string();
+  }
+  return format!("\x1b[{}m{}\x1b[0m", code, text);
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::PathBuf;
+
+  use crate::core::{BuildStage, SourceLocation};
+
+  use super::*;
+
+  fn loc(start_line: usize, start_col: usize, end_line: usize, end_col: usize) -> SourceLocation {
+    return SourceLocation { start_line, start_col, end_line, end_col };
+  }
+
+  #[test]
+  fn test_render_diagnostic() {
+    let mut error = BuildError::new(&PathBuf::from("/project/index.js"), BuildStage::TransformContents, "Unexpected token");
+    error.transformer = Some(String::from("DefaultJSTransformer javascript"));
+    error.highlights = vec![CodeHighlight { message: Some(String::from("here")), loc: loc(4, 9, 4, 10) }];
+    error.hints = vec![String::from("Remove the semicolon")];
+    error.documentation_url = Some(String::from("https://example.com/docs"));
+    error.code = Some(String::from("// 1\n// 2\n// 3\nlet a = ;\n// 5\n// 6\n// 7\n"));
+
+    assert_eq!(render_diagnostic(&error, false), [
+      "error: Unexpected token",
+      "  at /project/index.js:4:9 (DefaultJSTransformer javascript)",
+      "",
+      "    2 | // 2",
+      "    3 | // 3",
+      "  > 4 | let a = ;",
+      "      |         ^ here",
+      "    5 | // 5",
+      "    6 | // 6",
+      "",
+      "  hint: Remove the semicolon",
+      "  Learn more: https://example.com/docs",
+      "",
+    ].join("\n"));
+  }
+
+  #[test]
+  fn test_multiline_highlight() {
+    let highlights = vec![CodeHighlight { message: None, loc: loc(1, 3, 2, 4) }];
+    let frame = render_code_frame("a = {\n  b };", &highlights, RED, false);
+    assert_eq!(frame, [
+      "  > 1 | a = {",
+      "      |   ^^^",
+      "  > 2 |   b };",
+      "      | ^^^",
+      "",
+    ].join("\n"));
+  }
+
+  #[test]
+  fn test_without_location() {
+    let mut error = BuildError::new(&PathBuf::from("/project/missing.js"), BuildStage::ReadContents, "Unable to read file");
+    error.severity = Severity::Warning;
+    assert_eq!(render_diagnostic(&error, false), "warning: Unable to read file\n  at /project/missing.js (ReadContents)\n");
+  }
+}
@@ -0,0 +1,133 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use glob::glob;
+use serde_json::Value;
+
+/// Expands entry arguments into entry files. Arguments can be files,
+/// glob patterns or directories with a package.json#source field
+pub fn resolve_entries(inputs: &[String], cwd: &Path) -> Result<Vec<PathBuf>, String> {
+  return resolve_inputs(inputs, cwd, &mut Vec::new());
+}
+
+/// `parents` are the directories whose package.json#source led here, to stop on a cycle
+fn resolve_inputs(inputs: &[String], cwd: &Path, parents: &mut Vec<PathBuf>) -> Result<Vec<PathBuf>, String> {
+  let mut entries = Vec::<PathBuf>::new();
+
+  for input in inputs {
+    let input_path = cwd.join(input);
+
+    if is_glob(input) {
+      let pattern = input_path.to_str().unwrap();
+      let paths = glob(pattern).map_err(|e| format!("Invalid entry glob \"{}\": {}", input, e))?;
+      let mut matched = false;
+      for path in paths.flatten() {
+        if path.is_file() {
+          push_unique(&mut entries, path);
+          matched = true;
+        }
+      }
+      if !matched {
+        return Err(format!("No entries match \"{}\"", input));
+      }
+      continue;
+    }
+
+    if input_path.is_dir() {
+      for source in get_package_sources(&input_path, parents)? {
+        push_unique(&mut entries, source);
+      }
+      continue;
+    }
+
+    if !input_path.is_file() {
+      return Err(format!("Entry not found \"{}\"", input));
+    }
+    push_unique(&mut entries, input_path);
+  }
+
+  if entries.len() == 0 {
+    return Err(String::from("No entries given"));
+  }
+  return Ok(entries);
+}
+
+fn is_glob(input: &str) -> bool {
+  return input.contains('*') || input.contains('?') || input.contains('[');
+}
+
+fn push_unique(entries: &mut Vec<PathBuf>, entry: PathBuf) {
+  if !entries.contains(&entry) {
+    entries.push(entry);
+  }
+}
+
+/// Reads the entries listed in package.json#source, a string or an array of strings
+fn get_package_sources(dir: &Path, parents: &mut Vec<PathBuf>) -> Result<Vec<PathBuf>, String> {
+  let dir = fs::canonicalize(dir).map_err(|e| format!("Unable to read entry directory {:?}: {}", dir, e))?;
+  if parents.contains(&dir) {
+    return Err(format!("package.json#source in {:?} leads back to itself", dir));
+  }
+
+  let package_path = dir.join("package.json");
+  let contents = fs::read_to_string(&package_path)
+    .map_err(|_| format!("Entry directory has no package.json {:?}", dir))?;
+  let package: Value = serde_json::from_str(&contents)
+    .map_err(|e| format!("Unable to parse {:?}: {}", package_path, e))?;
+
+  let sources = match package.get("source") {
+    Some(Value::String(source)) => vec![source.clone()],
+    Some(Value::Array(sources)) => sources
+      .iter()
+      .filter_map(|s| s.as_str().map(|s| s.to_string()))
+      .collect(),
+    _ => return Err(format!("No \"source\" field in {:?}", package_path)),
+  };
+
+  parents.push(dir.clone());
+  let entries = resolve_inputs(&sources, &dir, parents);
+  parents.pop();
+  return entries;
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn fixtures() -> PathBuf {
+    return Path::new(env!("CARGO_MANIFEST_DIR")).join("../fixtures");
+  }
+
+  #[test]
+  fn test_multiple_entries() {
+    let entries = resolve_entries(
+      &[String::from("basic/index.html"), String::from("basic-nested/index.html")],
+      &fixtures(),
+    ).unwrap();
+    assert_eq!(entries, vec![
+      fixtures().join("basic/index.html"),
+      fixtures().join("basic-nested/index.html"),
+    ]);
+  }
+
+  #[test]
+  fn test_glob_entries() {
+    let entries = resolve_entries(&[String::from("basic-nested/scripts/*.js")], &fixtures()).unwrap();
+    let names: Vec<&str> = entries.iter().map(|e| e.file_name().unwrap().to_str().unwrap()).collect();
+    assert_eq!(names, vec!["a.js", "b.js", "index.js"]);
+  }
+
+  #[test]
+  fn test_source_cycle() {
+    let err = resolve_entries(&[String::from("entries-cycle")], &fixtures()).err().unwrap();
+    assert!(err.contains("leads back to itself"), "{}", err);
+    let err = resolve_entries(&[String::from("entries-cycle/a")], &fixtures()).err().unwrap();
+    assert!(err.contains("leads back to itself"), "{}", err);
+  }
+
+  #[test]
+  fn test_missing_entry() {
+    assert!(resolve_entries(&[String::from("basic/missing.html")], &fixtures()).is_err());
+    assert!(resolve_entries(&[String::from("basic/*.css")], &fixtures()).is_err());
+  }
+}
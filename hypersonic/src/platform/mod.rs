@@ -1,7 +1,15 @@
 #![allow(dead_code)]
 
 mod args;
+mod cli_reporter;
+mod code_frame;
+mod entries;
 mod log_level;
+mod scheduler_kind;
 
 pub use crate::platform::args::*;
+pub use crate::platform::cli_reporter::*;
+pub use crate::platform::code_frame::*;
+pub use crate::platform::entries::*;
 pub use crate::platform::log_level::*;
+pub use crate::platform::scheduler_kind::*;
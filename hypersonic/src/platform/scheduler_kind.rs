@@ -0,0 +1,23 @@
+use std::str::FromStr;
+
+#[derive(Clone, Debug)]
+pub enum SchedulerKind {
+    Broadcast,
+    WorkStealing,
+    Rayon,
+    SingleThreaded,
+}
+
+impl FromStr for SchedulerKind {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<SchedulerKind, ()> {
+        return match value {
+            "broadcast" => Ok(SchedulerKind::Broadcast),
+            "work-stealing" => Ok(SchedulerKind::WorkStealing),
+            "rayon" => Ok(SchedulerKind::Rayon),
+            "single-threaded" => Ok(SchedulerKind::SingleThreaded),
+            _ => Err(()),
+        };
+    }
+}
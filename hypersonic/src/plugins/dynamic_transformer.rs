@@ -0,0 +1,205 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use libloading::{Library, Symbol};
+
+use crate::core::Asset;
+use crate::transformation::{Transformer, TransformerContext, TransformerError, TransformerResult};
+
+use super::{PLUGIN_ABI_VERSION, PLUGIN_ENTRY_SYMBOL, PluginBuffer, PluginRequest, PluginResponse, PluginVTable};
+
+/// A loaded plugin, shared by every transformer created from it
+pub struct PluginLibrary {
+    name: String,
+    vtable: &'static PluginVTable,
+    /// Kept loaded for as long as the vtable is in use
+    _library: Option<Library>,
+}
+
+impl PluginLibrary {
+    pub fn load(file_path: &Path) -> Result<Arc<Self>, String> {
+        let library = unsafe { Library::new(file_path) };
+        if library.is_err() {
+            return Err(format!("Unable to load plugin: {}", library.err().unwrap()));
+        }
+        let library = library.unwrap();
+
+        let vtable = {
+            let entry: Result<Symbol<extern "C" fn() -> *const PluginVTable>, _> = unsafe { library.get(PLUGIN_ENTRY_SYMBOL) };
+            if entry.is_err() {
+                return Err(String::from("Not a hypersonic plugin, \"hypersonic_plugin\" is not exported"));
+            }
+            // The library outlives the vtable, it is dropped with the PluginLibrary
+            unsafe { &*entry.unwrap()() }
+        };
+
+        return PluginLibrary::from_vtable(vtable, Some(library));
+    }
+
+    /// Uses a plugin linked into the current binary
+    pub fn from_vtable(vtable: &'static PluginVTable, library: Option<Library>) -> Result<Arc<Self>, String> {
+        if vtable.abi_version != PLUGIN_ABI_VERSION {
+            return Err(format!(
+                "Plugin was built for ABI version {}, this build of hypersonic uses {}",
+                vtable.abi_version,
+                PLUGIN_ABI_VERSION,
+            ));
+        }
+
+        let name = (vtable.get_name)();
+        let name_bytes = unsafe { name.as_slice() }.to_vec();
+        (vtable.free)(name);
+
+        return Ok(Arc::new(PluginLibrary {
+            name: String::from_utf8_lossy(&name_bytes).to_string(),
+            vtable,
+            _library: library,
+        }));
+    }
+
+    pub fn get_name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// A panic can't be caught here, unwinding out of an `extern "C"` function aborts the
+    /// process. `run_plugin_transform` catches them on the plugin's side and answers with an error
+    fn call(&self, request: &[u8]) -> Vec<u8> {
+        let buffer = (self.vtable.transform)(PluginBuffer::borrowed(request));
+        let bytes = unsafe { buffer.as_slice() }.to_vec();
+        (self.vtable.free)(buffer);
+        return bytes;
+    }
+}
+
+/// Runs a transformer exported from a shared library with `export_transformer!`
+pub struct DynamicTransformer {
+    plugin: Arc<PluginLibrary>,
+}
+
+impl DynamicTransformer {
+    pub fn new(plugin: Arc<PluginLibrary>) -> Self {
+        return DynamicTransformer {
+            plugin,
+        };
+    }
+}
+
+impl Transformer for DynamicTransformer {
+    fn get_name(&self) -> String {
+        return self.plugin.get_name().to_string();
+    }
+
+    fn transform(
+        &self,
+        ctx: &TransformerContext,
+        asset: &mut Asset,
+    ) -> TransformerResult {
+        let request = PluginRequest::new(ctx, asset);
+        if request.is_err() {
+            return TransformerResult::Err(TransformerError::new(&request.err().unwrap()));
+        }
+
+        let response = self.plugin.call(&serde_json::to_vec(&request.unwrap()).unwrap());
+        let response: Result<PluginResponse, _> = serde_json::from_slice(&response);
+        if response.is_err() {
+            return TransformerResult::Err(TransformerError::new(&format!(
+                "Invalid response from plugin: {}",
+                response.err().unwrap(),
+            )));
+        }
+
+        return response.unwrap().apply(ctx, asset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::core::{AssetGraph, AssetKey, BuildMode, Content, Dependency, DependencyKind, Environment, EnvironmentContext};
+    use crate::platform::LogLevel;
+    use crate::resolver::{Resolver, ResolverOptions};
+    use crate::transformation::ConfigCache;
+
+    use super::*;
+
+    struct TestTransformer {}
+
+    impl Transformer for TestTransformer {
+        fn get_name(&self) -> String {
+            return String::from("TestTransformer");
+        }
+
+        fn transform(&self, ctx: &TransformerContext, asset: &mut Asset) -> TransformerResult {
+            let content = asset.content.as_str().unwrap().to_string();
+            if content == "panic" {
+                panic!("transformer failed");
+            }
+            let file_path = asset.file_path.with_file_name("dependency.js");
+            ctx.add_dependency(Dependency::new("./dependency.js", DependencyKind::Import, file_path));
+            asset.content = Content::from(format!("/* banner */{}", content));
+            return TransformerResult::Continue;
+        }
+    }
+
+    crate::export_transformer!(TestTransformer {});
+
+    fn transform(content: &str) -> (TransformerResult, Asset, Vec<Dependency>) {
+        let env = Environment::new(EnvironmentContext::Browser, BuildMode::Development);
+        let key = AssetKey {
+            file_path: PathBuf::from("/project/index.js"),
+            query: None,
+            pipeline: None,
+            unique_key: None,
+            env: env.clone(),
+        };
+        let mut asset = Asset::new(&key);
+        asset.content = Content::from(content.to_string());
+
+        let ctx = TransformerContext::new(
+            0,
+            env,
+            AssetGraph::new(),
+            LogLevel::Info,
+            Arc::new(Resolver::new(ResolverOptions::with_project_root(PathBuf::from("/project")))),
+            Arc::new(ConfigCache::new()),
+        );
+
+        let plugin = PluginLibrary::from_vtable(unsafe { &*hypersonic_plugin() }, None).unwrap();
+        let transformer = DynamicTransformer::new(plugin);
+        assert_eq!(transformer.get_name(), "TestTransformer");
+
+        let result = transformer.transform(&ctx, &mut asset);
+        return (result, asset, ctx.take_dependencies());
+    }
+
+    #[test]
+    fn test_transform_through_abi() {
+        let (result, asset, dependencies) = transform("code");
+        assert_eq!(result.get_name(), "Continue");
+        assert_eq!(asset.content.as_bytes(), b"/* banner */code");
+        assert_eq!(dependencies.len(), 1);
+    }
+
+    #[test]
+    fn test_plugin_panic_is_an_error() {
+        let (result, asset, dependencies) = transform("panic");
+        assert_eq!(result.err().message, "Plugin panicked: transformer failed");
+        assert_eq!(asset.content.as_bytes(), b"panic");
+        assert_eq!(dependencies.len(), 0);
+    }
+
+    #[test]
+    fn test_abi_version_mismatch() {
+        static OUTDATED: PluginVTable = PluginVTable {
+            abi_version: 0,
+            get_name: __hypersonic_get_name,
+            transform: __hypersonic_transform,
+            free: __hypersonic_free,
+        };
+        let err = PluginLibrary::from_vtable(&OUTDATED, None).err().unwrap();
+        assert_eq!(err, format!("Plugin was built for ABI version 0, this build of hypersonic uses {}", PLUGIN_ABI_VERSION));
+
+        assert!(PluginLibrary::load(Path::new("/missing/libplugin.so")).is_err());
+    }
+}
@@ -0,0 +1,9 @@
+mod dynamic_transformer;
+mod plugin_abi;
+mod plugin_message;
+mod process_transformer;
+
+pub use crate::plugins::dynamic_transformer::*;
+pub use crate::plugins::plugin_abi::*;
+pub use crate::plugins::plugin_message::*;
+pub use crate::plugins::process_transformer::*;
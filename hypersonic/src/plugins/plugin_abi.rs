@@ -0,0 +1,212 @@
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::slice;
+use std::sync::{Arc, Mutex};
+
+use crate::resolver::{Resolver, ResolverOptions};
+use crate::transformation::{ConfigCache, Transformer};
+
+use super::{PluginRequest, PluginResponse};
+
+/// Bumped whenever `PluginVTable` or the messages change incompatibly
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// Symbol every plugin library exports, returning its `PluginVTable`
+pub const PLUGIN_ENTRY_SYMBOL: &[u8] = b"hypersonic_plugin\0";
+
+/// Bytes passed across the plugin boundary.
+/// Buffers returned by a plugin are owned by it and handed back through `free`
+#[repr(C)]
+pub struct PluginBuffer {
+    pub ptr: *mut u8,
+    pub len: usize,
+    pub capacity: usize,
+}
+
+/// The functions a plugin exposes. Only `abi_version` can be relied on
+/// before it has been checked against `PLUGIN_ABI_VERSION`
+#[repr(C)]
+pub struct PluginVTable {
+    pub abi_version: u32,
+    pub get_name: extern "C" fn() -> PluginBuffer,
+    /// Takes a JSON `PluginRequest`, returns a JSON `PluginResponse`
+    pub transform: extern "C" fn(request: PluginBuffer) -> PluginBuffer,
+    pub free: extern "C" fn(buffer: PluginBuffer),
+}
+
+impl PluginBuffer {
+    pub fn from_vec(bytes: Vec<u8>) -> Self {
+        let mut bytes = std::mem::ManuallyDrop::new(bytes);
+        return PluginBuffer {
+            ptr: bytes.as_mut_ptr(),
+            len: bytes.len(),
+            capacity: bytes.capacity(),
+        };
+    }
+
+    /// A view of bytes owned by the caller, must not be freed
+    pub fn borrowed(bytes: &[u8]) -> Self {
+        return PluginBuffer {
+            ptr: bytes.as_ptr() as *mut u8,
+            len: bytes.len(),
+            capacity: 0,
+        };
+    }
+
+    /// # Safety
+    /// The buffer must point to `len` readable bytes for the returned lifetime
+    pub unsafe fn as_slice<'a>(&self) -> &'a [u8] {
+        if self.len == 0 {
+            return &[];
+        }
+        return slice::from_raw_parts(self.ptr, self.len);
+    }
+
+    /// # Safety
+    /// The buffer must have been created by `from_vec` in the same library
+    pub unsafe fn into_vec(self) -> Vec<u8> {
+        return Vec::from_raw_parts(self.ptr, self.len, self.capacity);
+    }
+}
+
+/// What a plugin library keeps between calls: its transformer, and the resolver and
+/// config cache shared by the contexts it runs in, so each package.json, tsconfig and
+/// file system lookup is done once like on the host
+pub struct PluginState {
+    transformer: Box<dyn Transformer>,
+    caches: Mutex<Option<(PathBuf, Arc<Resolver>, Arc<ConfigCache>)>>,
+}
+
+impl PluginState {
+    pub fn new(transformer: Box<dyn Transformer>) -> Self {
+        return PluginState {
+            transformer,
+            caches: Mutex::new(None),
+        };
+    }
+
+    /// The resolver and config cache for `project_root`, created by the first request.
+    /// A request from another project replaces them
+    pub fn caches(&self, project_root: &Path) -> (Arc<Resolver>, Arc<ConfigCache>) {
+        let mut caches = self.caches.lock().unwrap();
+        if let Some((root, resolver, config_cache)) = caches.as_ref() {
+            if root == project_root {
+                return (resolver.clone(), config_cache.clone());
+            }
+        }
+
+        let resolver = Arc::new(Resolver::new(ResolverOptions::with_project_root(project_root.to_path_buf())));
+        let config_cache = Arc::new(ConfigCache::new());
+        *caches = Some((project_root.to_path_buf(), resolver.clone(), config_cache.clone()));
+        return (resolver, config_cache);
+    }
+}
+
+/// Plugin side of `PluginVTable::transform`. Panics in the transformer are
+/// caught here and returned as errors, as unwinding can't cross the boundary
+pub fn run_plugin_transform(state: &PluginState, request: PluginBuffer) -> PluginBuffer {
+    let request = unsafe { request.as_slice() };
+
+    let response = panic::catch_unwind(AssertUnwindSafe(|| {
+        let request: Result<PluginRequest, _> = serde_json::from_slice(request);
+        if request.is_err() {
+            return PluginResponse::error(&format!("Invalid request: {}", request.err().unwrap()));
+        }
+        let request = request.unwrap();
+        let (resolver, config_cache) = state.caches(&request.project_root);
+        return PluginResponse::from_transformer(state.transformer.as_ref(), request, resolver, config_cache);
+    }));
+
+    let response = match response {
+        Ok(response) => response,
+        Err(panic) => PluginResponse::error(&format!("Plugin panicked: {}", panic_message(&panic))),
+    };
+
+    return PluginBuffer::from_vec(serde_json::to_vec(&response).unwrap());
+}
+
+/// Plugin side of `PluginVTable::get_name`
+pub fn run_plugin_get_name(state: &PluginState) -> PluginBuffer {
+    let name = panic::catch_unwind(AssertUnwindSafe(|| state.transformer.get_name()));
+    return PluginBuffer::from_vec(name.unwrap_or(String::from("Unnamed Plugin")).into_bytes());
+}
+
+pub fn panic_message(panic: &Box<dyn Any + Send>) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        return message.to_string();
+    }
+    if let Some(message) = panic.downcast_ref::<String>() {
+        return message.clone();
+    }
+    return String::from("unknown panic");
+}
+
+/// Exports a transformer from a `cdylib` so it can be listed under `plugins` in `.hypersonicrc`
+///
+/// ```ignore
+/// hypersonic::export_transformer!(MyTransformer::new());
+/// ```
+#[macro_export]
+macro_rules! export_transformer {
+    ($transformer:expr) => {
+        fn __hypersonic_state() -> &'static $crate::plugins::PluginState {
+            static STATE: std::sync::OnceLock<$crate::plugins::PluginState> = std::sync::OnceLock::new();
+            return STATE.get_or_init(|| $crate::plugins::PluginState::new(Box::new($transformer)));
+        }
+
+        extern "C" fn __hypersonic_get_name() -> $crate::plugins::PluginBuffer {
+            return $crate::plugins::run_plugin_get_name(__hypersonic_state());
+        }
+
+        extern "C" fn __hypersonic_transform(request: $crate::plugins::PluginBuffer) -> $crate::plugins::PluginBuffer {
+            return $crate::plugins::run_plugin_transform(__hypersonic_state(), request);
+        }
+
+        extern "C" fn __hypersonic_free(buffer: $crate::plugins::PluginBuffer) {
+            drop(unsafe { buffer.into_vec() });
+        }
+
+        static __HYPERSONIC_VTABLE: $crate::plugins::PluginVTable = $crate::plugins::PluginVTable {
+            abi_version: $crate::plugins::PLUGIN_ABI_VERSION,
+            get_name: __hypersonic_get_name,
+            transform: __hypersonic_transform,
+            free: __hypersonic_free,
+        };
+
+        #[no_mangle]
+        pub extern "C" fn hypersonic_plugin() -> *const $crate::plugins::PluginVTable {
+            return &__HYPERSONIC_VTABLE;
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::Asset;
+    use crate::transformation::{TransformerContext, TransformerResult};
+
+    use super::*;
+
+    struct Noop {}
+
+    impl Transformer for Noop {
+        fn transform(&self, _ctx: &TransformerContext, _asset: &mut Asset) -> TransformerResult {
+            return TransformerResult::Continue;
+        }
+    }
+
+    #[test]
+    fn test_caches_are_kept_per_project_root() {
+        let state = PluginState::new(Box::new(Noop {}));
+        let (resolver, config_cache) = state.caches(Path::new("/project"));
+
+        let (same_resolver, same_config_cache) = state.caches(Path::new("/project"));
+        assert!(Arc::ptr_eq(&resolver, &same_resolver));
+        assert!(Arc::ptr_eq(&config_cache, &same_config_cache));
+
+        let (other_resolver, _) = state.caches(Path::new("/other"));
+        assert!(!Arc::ptr_eq(&resolver, &other_resolver));
+        assert_eq!(other_resolver.options.project_root, PathBuf::from("/other"));
+    }
+}
@@ -0,0 +1,366 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::{Asset, AssetGraph, AssetKey, Content, Dependency, DependencyKind, Environment, InlineAsset, Invalidation, SourceLocation, SourceMap, compose_source_maps};
+use crate::platform::LogLevel;
+use crate::resolver::Resolver;
+use crate::transformation::{ConfigCache, Transformer, TransformerContext, TransformerError, TransformerResult};
+
+/// What a plugin is sent for each asset, serialized as JSON
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginRequest {
+    pub file_path: PathBuf,
+    pub asset_type: String,
+    pub content: String,
+    pub env: Environment,
+    pub project_root: PathBuf,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PluginResult {
+    /// Also what a transformer's `Replace` becomes. The response carries the new content,
+    /// type and map, and the host applies them in one step once the plugin has returned,
+    /// so nothing is applied if the plugin fails either way
+    Continue,
+    Skip,
+    Break,
+    Error,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginDependency {
+    pub specifier: String,
+    pub kind: DependencyKind,
+    /// The resolved file. Left out, the host resolves `specifier` from the asset
+    #[serde(default)]
+    pub file_path: Option<PathBuf>,
+    #[serde(default)]
+    pub query: Option<String>,
+    #[serde(default)]
+    pub pipeline: Option<String>,
+    #[serde(default)]
+    pub loc: Option<SourceLocation>,
+    /// Left for the runtime to load, `filePath` is ignored
+    #[serde(default)]
+    pub is_external: bool,
+}
+
+/// A plugin's reply. Content and type are only changed when they are set
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginResponse {
+    pub result: PluginResult,
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default)]
+    pub asset_type: Option<String>,
+    /// Source map JSON from the new content to the content the plugin was sent.
+    /// The asset's map is dropped when the content changes without one
+    #[serde(default)]
+    pub map: Option<String>,
+    #[serde(default)]
+    pub dependencies: Vec<PluginDependency>,
+    #[serde(default)]
+    pub inline_assets: Vec<InlineAsset>,
+    /// Files read or looked for, other than the asset itself
+    #[serde(default)]
+    pub invalidations: Vec<Invalidation>,
+    /// Set with `Error`
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub loc: Option<SourceLocation>,
+}
+
+impl PluginRequest {
+    pub fn new(ctx: &TransformerContext, asset: &mut Asset) -> Result<Self, String> {
+        let content = asset.content.as_str();
+        if content.is_err() {
+            return Err(format!("Plugins can only transform text: {}", content.err().unwrap()));
+        }
+
+        return Ok(PluginRequest {
+            file_path: asset.file_path.clone(),
+            asset_type: asset.asset_type.clone(),
+            content: content.unwrap().to_string(),
+            env: ctx.env.clone(),
+            project_root: ctx.resolver.options.project_root.clone(),
+        });
+    }
+}
+
+impl PluginResponse {
+    pub fn error(message: &str) -> Self {
+        return PluginResponse {
+            result: PluginResult::Error,
+            content: None,
+            asset_type: None,
+            map: None,
+            dependencies: Vec::new(),
+            inline_assets: Vec::new(),
+            invalidations: Vec::new(),
+            message: Some(message.to_string()),
+            loc: None,
+        };
+    }
+
+    /// Runs a transformer on the plugin's side of the boundary, against a context of
+    /// its own using the plugin's resolver and config cache, and collects what it did
+    /// into a response
+    pub fn from_transformer(
+        transformer: &dyn Transformer,
+        request: PluginRequest,
+        resolver: Arc<Resolver>,
+        config_cache: Arc<ConfigCache>,
+    ) -> Self {
+        let key = AssetKey {
+            file_path: request.file_path,
+            query: None,
+            pipeline: None,
+            unique_key: None,
+            env: request.env.clone(),
+        };
+        let mut asset = Asset::new(&key);
+        asset.asset_type = request.asset_type;
+        asset.content = Content::from(request.content);
+
+        let ctx = TransformerContext::new(
+            0,
+            request.env,
+            AssetGraph::new(),
+            LogLevel::Info,
+            resolver,
+            config_cache,
+        );
+
+        let result = match transformer.transform(&ctx, &mut asset) {
+            TransformerResult::Continue => PluginResult::Continue,
+            TransformerResult::Skip => PluginResult::Skip,
+            TransformerResult::Break => PluginResult::Break,
+            TransformerResult::Replace(replacement) => {
+                asset.content = replacement.content;
+                if let Some(asset_type) = replacement.asset_type {
+                    asset.asset_type = asset_type;
+                }
+                if replacement.map.is_some() {
+                    asset.map = replacement.map;
+                }
+                PluginResult::Continue
+            }
+            TransformerResult::Err(err) => {
+                let mut response = PluginResponse::error(&err.message);
+                response.loc = err.loc;
+                response.invalidations = ctx.take_invalidations();
+                return response;
+            }
+        };
+
+        let content = asset.content.as_str();
+        if content.is_err() {
+            return PluginResponse::error(&content.err().unwrap());
+        }
+
+        let map = match &mut asset.map {
+            Some(map) => match map.to_json(None) {
+                Ok(map) => Some(map),
+                Err(err) => return PluginResponse::error(&format!("Unable to serialize source map: {}", err)),
+            },
+            None => None,
+        };
+
+        let dependencies = ctx.take_dependencies()
+            .into_iter()
+            .map(|dependency| PluginDependency {
+                specifier: dependency.specifier,
+                kind: dependency.kind,
+                file_path: Some(dependency.file_path),
+                query: dependency.query,
+                pipeline: dependency.pipeline,
+                loc: dependency.loc,
+                is_external: dependency.is_external,
+            })
+            .collect();
+
+        return PluginResponse {
+            result,
+            content: Some(content.unwrap().to_string()),
+            asset_type: Some(asset.asset_type.clone()),
+            map,
+            dependencies,
+            inline_assets: ctx.take_inline_assets().into_iter().map(|(_, inline)| inline).collect(),
+            invalidations: ctx.take_invalidations(),
+            message: None,
+            loc: None,
+        };
+    }
+
+    /// Applies the reply to the asset and context on the host's side
+    pub fn apply(self, ctx: &TransformerContext, asset: &mut Asset) -> TransformerResult {
+        for invalidation in self.invalidations {
+            ctx.add_invalidation(invalidation);
+        }
+
+        match self.result {
+            PluginResult::Skip => return TransformerResult::Skip,
+            PluginResult::Error => {
+                let mut error = TransformerError::new(&self.message.unwrap_or(String::from("Plugin failed")));
+                error.loc = self.loc;
+                return TransformerResult::Err(error);
+            }
+            _ => {}
+        }
+
+        for plugin_dependency in self.dependencies {
+            let mut dependency = Dependency::new(
+                &plugin_dependency.specifier,
+                plugin_dependency.kind,
+                PathBuf::new(),
+            );
+            dependency.pipeline = plugin_dependency.pipeline;
+            dependency.loc = plugin_dependency.loc;
+
+            let Some(file_path) = plugin_dependency.file_path else {
+                let resolution = ctx.resolve(&dependency.specifier, &asset.file_path, &dependency.kind);
+                if resolution.is_err() {
+                    let mut error = TransformerError::new(&format!(
+                        "Unable to resolve \"{}\": {}",
+                        dependency.specifier,
+                        resolution.err().unwrap(),
+                    ));
+                    error.loc = dependency.loc;
+                    return TransformerResult::Err(error);
+                }
+                ctx.add_resolved_dependency(dependency, resolution.unwrap());
+                continue;
+            };
+
+            dependency.file_path = file_path;
+            dependency.query = plugin_dependency.query;
+            dependency.is_external = plugin_dependency.is_external;
+            ctx.add_dependency(dependency);
+        }
+
+        let has_map = self.map.is_some();
+        if let Some(map) = self.map {
+            let project_root = ctx.resolver.options.project_root.to_string_lossy();
+            let map = SourceMap::from_json(&project_root, &map);
+            if map.is_err() {
+                return TransformerResult::Err(TransformerError::new(&format!("Invalid source map: {}", map.err().unwrap())));
+            }
+            let composed = compose_source_maps(asset.map.take(), map.unwrap());
+            if composed.is_err() {
+                return TransformerResult::Err(TransformerError::new(&composed.err().unwrap()));
+            }
+            asset.map = Some(composed.unwrap());
+        }
+
+        for inline_asset in self.inline_assets {
+            ctx.add_inline_asset(&asset.file_path, inline_asset);
+        }
+
+        if let Some(content) = self.content {
+            if !has_map && content.as_bytes() != asset.content.as_bytes() {
+                asset.map = None;
+            }
+            asset.content = Content::from(content);
+        }
+        if let Some(asset_type) = self.asset_type {
+            asset.asset_type = asset_type;
+        }
+
+        if self.result == PluginResult::Break {
+            return TransformerResult::Break;
+        }
+        return TransformerResult::Continue;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::core::{BuildMode, EnvironmentContext};
+    use crate::resolver::ResolverOptions;
+
+    use super::*;
+
+    fn fixture() -> PathBuf {
+        return Path::new(env!("CARGO_MANIFEST_DIR")).join("../fixtures/builtins").canonicalize().unwrap();
+    }
+
+    fn context(project_root: &Path) -> (Asset, TransformerContext) {
+        let env = Environment::new(EnvironmentContext::Browser, BuildMode::Development);
+        let key = AssetKey {
+            file_path: project_root.join("index.js"),
+            query: None,
+            pipeline: None,
+            unique_key: None,
+            env: env.clone(),
+        };
+        let ctx = TransformerContext::new(
+            0,
+            env,
+            AssetGraph::new(),
+            LogLevel::Info,
+            Arc::new(Resolver::new(ResolverOptions::with_project_root(project_root.to_path_buf()))),
+            Arc::new(ConfigCache::new()),
+        );
+        return (Asset::new(&key), ctx);
+    }
+
+    #[test]
+    fn test_host_resolves_dependencies_without_file_path() {
+        let project_root = fixture();
+        let (mut asset, ctx) = context(&project_root);
+
+        let response: PluginResponse = serde_json::from_str(r#"{
+            "result": "continue",
+            "dependencies": [
+                { "specifier": "./path.js", "kind": "import" },
+                { "specifier": "fs", "kind": "require" }
+            ]
+        }"#).unwrap();
+        assert_eq!(response.apply(&ctx, &mut asset).get_name(), "Continue");
+
+        let dependencies = ctx.take_dependencies();
+        assert_eq!(dependencies.len(), 1);
+        assert_eq!(dependencies[0].file_path, project_root.join("path.js"));
+
+        let inline_assets = ctx.take_inline_assets();
+        assert_eq!(inline_assets.len(), 1);
+        assert_eq!(inline_assets[0].0.specifier, "fs");
+        assert_eq!(inline_assets[0].0.file_path, PathBuf::from("empty:fs"));
+    }
+
+    #[test]
+    fn test_unresolved_dependency_fails() {
+        let (mut asset, ctx) = context(&fixture());
+
+        let response: PluginResponse = serde_json::from_str(r#"{
+            "result": "continue",
+            "dependencies": [{ "specifier": "./missing.js", "kind": "import" }]
+        }"#).unwrap();
+        let err = response.apply(&ctx, &mut asset).err();
+        assert!(err.message.starts_with("Unable to resolve \"./missing.js\": "), "{}", err.message);
+    }
+
+    #[test]
+    fn test_new_content_without_map_drops_map() {
+        let (mut asset, ctx) = context(&fixture());
+        asset.content = Content::from(String::from("code"));
+
+        let response: PluginResponse = serde_json::from_str(r#"{ "result": "continue", "content": "code" }"#).unwrap();
+        asset.map = Some(SourceMap::new("/project"));
+        response.apply(&ctx, &mut asset);
+        assert!(asset.map.is_some());
+
+        let response: PluginResponse = serde_json::from_str(r#"{ "result": "continue", "content": "changed" }"#).unwrap();
+        response.apply(&ctx, &mut asset);
+        assert!(asset.map.is_none());
+    }
+}
@@ -0,0 +1,432 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle, ThreadId};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::Asset;
+use crate::transformation::{Transformer, TransformerContext, TransformerError, TransformerResult};
+
+use super::{PluginRequest, PluginResponse};
+
+/// Lines of stderr kept to explain why a plugin process failed
+const STDERR_LINES: usize = 20;
+
+/// How long a plugin that stopped answering has to exit on its own before it is killed
+const EXIT_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug)]
+pub struct ProcessOptions {
+    /// Program followed by its arguments
+    pub command: Vec<String>,
+    pub cwd: Option<PathBuf>,
+    /// How long a process has to answer a request before it is restarted
+    pub timeout: Duration,
+}
+
+impl ProcessOptions {
+    pub fn new(command: Vec<String>) -> Self {
+        return ProcessOptions {
+            command,
+            cwd: None,
+            timeout: Duration::from_secs(30),
+        };
+    }
+}
+
+/// Runs a transformer implemented by another program, in any language.
+///
+/// Each worker thread gets its own long lived process. Requests are written to
+/// its stdin as one JSON-RPC 2.0 message per line, with a `transform` method
+/// taking a `PluginRequest`, and the process answers each on one line of stdout
+/// with a `PluginResponse` result. Logs should go to stderr.
+/// A process that crashes or times out is restarted for the next request.
+#[derive(Clone)]
+pub struct ProcessTransformer {
+    name: String,
+    options: Arc<ProcessOptions>,
+    processes: Arc<Mutex<HashMap<ThreadId, Arc<Mutex<PluginProcess>>>>>,
+}
+
+impl ProcessTransformer {
+    pub fn new(name: &str, options: ProcessOptions) -> Self {
+        return ProcessTransformer {
+            name: name.to_string(),
+            options: Arc::new(options),
+            processes: Arc::new(Mutex::new(HashMap::new())),
+        };
+    }
+
+    fn get_process(&self) -> Result<Arc<Mutex<PluginProcess>>, String> {
+        let mut processes = self.processes.lock().unwrap();
+        let thread_id = thread::current().id();
+
+        if let Some(process) = processes.get(&thread_id) {
+            if !process.lock().unwrap().has_exited() {
+                return Ok(process.clone());
+            }
+        }
+
+        let process = Arc::new(Mutex::new(PluginProcess::spawn(&self.options)?));
+        processes.insert(thread_id, process.clone());
+        return Ok(process);
+    }
+
+    fn discard_process(&self) {
+        self.processes.lock().unwrap().remove(&thread::current().id());
+    }
+}
+
+impl Transformer for ProcessTransformer {
+    fn get_name(&self) -> String {
+        return self.name.clone();
+    }
+
+    fn transform(
+        &self,
+        ctx: &TransformerContext,
+        asset: &mut Asset,
+    ) -> TransformerResult {
+        let request = PluginRequest::new(ctx, asset);
+        if request.is_err() {
+            return TransformerResult::Err(TransformerError::new(&request.err().unwrap()));
+        }
+
+        let process = self.get_process();
+        if process.is_err() {
+            return TransformerResult::Err(TransformerError::new(&process.err().unwrap()));
+        }
+        let process = process.unwrap();
+
+        let response = process.lock().unwrap().request(&request.unwrap(), self.options.timeout);
+        if response.is_err() {
+            self.discard_process();
+            return TransformerResult::Err(TransformerError::new(&response.err().unwrap()));
+        }
+
+        return response.unwrap().apply(ctx, asset);
+    }
+}
+
+#[derive(Serialize)]
+struct RpcRequest<'a> {
+    jsonrpc: &'static str,
+    id: u64,
+    method: &'static str,
+    params: &'a PluginRequest,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    id: u64,
+    result: Option<PluginResponse>,
+    error: Option<RpcError>,
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    message: String,
+}
+
+struct PluginProcess {
+    child: Child,
+    stdin: Sender<Vec<u8>>,
+    stdout: Receiver<String>,
+    stderr: Arc<Mutex<VecDeque<String>>>,
+    stderr_reader: Option<JoinHandle<()>>,
+    next_id: u64,
+}
+
+impl PluginProcess {
+    fn spawn(options: &ProcessOptions) -> Result<Self, String> {
+        if options.command.len() == 0 {
+            return Err(String::from("Plugin command is empty"));
+        }
+
+        let mut command = Command::new(&options.command[0]);
+        command
+            .args(&options.command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(cwd) = &options.cwd {
+            command.current_dir(cwd);
+        }
+
+        let child = command.spawn();
+        if child.is_err() {
+            return Err(format!(
+                "Unable to start plugin \"{}\": {}",
+                options.command.join(" "),
+                child.err().unwrap(),
+            ));
+        }
+        let mut child = child.unwrap();
+
+        let mut stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let stderr = BufReader::new(child.stderr.take().unwrap());
+
+        // Writing and reading happen on threads of their own so requests can time out,
+        // even when the process stops reading and a large request fills the pipe
+        let (stdin_sender, stdin_receiver) = mpsc::channel::<Vec<u8>>();
+        thread::spawn(move || {
+            for message in stdin_receiver {
+                if stdin.write_all(&message).and_then(|_| stdin.flush()).is_err() {
+                    return;
+                }
+            }
+        });
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in stdout.lines() {
+                if line.is_err() || sender.send(line.unwrap()).is_err() {
+                    return;
+                }
+            }
+        });
+
+        let stderr_lines = Arc::new(Mutex::new(VecDeque::new()));
+        let stderr_writer = stderr_lines.clone();
+        let stderr_reader = thread::spawn(move || {
+            for line in stderr.lines() {
+                if line.is_err() {
+                    return;
+                }
+                let mut lines = stderr_writer.lock().unwrap();
+                if lines.len() == STDERR_LINES {
+                    lines.pop_front();
+                }
+                lines.push_back(line.unwrap());
+            }
+        });
+
+        return Ok(PluginProcess {
+            child,
+            stdin: stdin_sender,
+            stdout: receiver,
+            stderr: stderr_lines,
+            stderr_reader: Some(stderr_reader),
+            next_id: 0,
+        });
+    }
+
+    fn has_exited(&mut self) -> bool {
+        return !matches!(self.child.try_wait(), Ok(None));
+    }
+
+    /// Errors mean the process can't be trusted with more requests
+    fn request(&mut self, params: &PluginRequest, timeout: Duration) -> Result<PluginResponse, String> {
+        self.next_id += 1;
+        let request = RpcRequest {
+            jsonrpc: "2.0",
+            id: self.next_id,
+            method: "transform",
+            params,
+        };
+
+        let mut message = serde_json::to_vec(&request).unwrap();
+        message.push(b'\n');
+        if self.stdin.send(message).is_err() {
+            return Err(self.exit_message("Plugin process exited"));
+        }
+
+        let line = match self.stdout.recv_timeout(timeout) {
+            Ok(line) => line,
+            Err(RecvTimeoutError::Timeout) => {
+                return Err(format!("Plugin process timed out after {} ms", timeout.as_millis()));
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(self.exit_message("Plugin process exited"));
+            }
+        };
+
+        let response: Result<RpcResponse, _> = serde_json::from_str(&line);
+        if response.is_err() {
+            return Err(format!("Invalid response from plugin process: {}\n  {}", response.err().unwrap(), line));
+        }
+        let response = response.unwrap();
+
+        if response.id != self.next_id {
+            return Err(format!("Plugin process answered request {} instead of {}", response.id, self.next_id));
+        }
+        if let Some(error) = response.error {
+            return Ok(PluginResponse::error(&error.message));
+        }
+        if response.result.is_none() {
+            return Err(String::from("Plugin process response has neither a result nor an error"));
+        }
+        return Ok(response.result.unwrap());
+    }
+
+    fn exit_message(&mut self, message: &str) -> String {
+        let mut message = message.to_string();
+        match self.wait_for_exit(EXIT_TIMEOUT) {
+            Some(status) => message.push_str(&format!(" ({})", status)),
+            // Closing stdout doesn't mean the process exits, it could keep running forever
+            None => {
+                let _ = self.child.kill();
+                let _ = self.child.wait();
+                message = String::from("Plugin process closed stdout without exiting and was killed");
+            }
+        }
+
+        // Stderr is closed once the process has exited, unless something it started still
+        // holds it open, so the reader only gets a moment to catch up on what was written
+        if let Some(stderr_reader) = self.stderr_reader.take() {
+            let deadline = Instant::now() + EXIT_TIMEOUT;
+            while !stderr_reader.is_finished() && Instant::now() < deadline {
+                thread::sleep(Duration::from_millis(10));
+            }
+            if stderr_reader.is_finished() {
+                let _ = stderr_reader.join();
+            }
+        }
+        for line in self.stderr.lock().unwrap().iter() {
+            message.push_str(&format!("\n  {}", line));
+        }
+        return message;
+    }
+
+    fn wait_for_exit(&mut self, timeout: Duration) -> Option<ExitStatus> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self.child.try_wait() {
+                Ok(Some(status)) => return Some(status),
+                Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(10)),
+                _ => return None,
+            }
+        }
+    }
+}
+
+impl Drop for PluginProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::fs;
+
+    use crate::core::{AssetGraph, AssetKey, BuildMode, Content, Environment, EnvironmentContext};
+    use crate::platform::LogLevel;
+    use crate::resolver::{Resolver, ResolverOptions};
+    use crate::transformation::ConfigCache;
+
+    use super::*;
+
+    /// Answers every request with `content` replaced
+    const REPLY: &str = r#"while read line; do
+        id=$(echo "$line" | sed 's/^{"jsonrpc":"2.0","id":\([0-9]*\).*/\1/')
+        echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":{\"result\":\"continue\",\"content\":\"replaced $id\"}}"
+    done"#;
+
+    fn transformer(script: &str, timeout: Duration) -> ProcessTransformer {
+        let mut options = ProcessOptions::new(vec![String::from("sh"), String::from("-c"), script.to_string()]);
+        options.timeout = timeout;
+        return ProcessTransformer::new("test", options);
+    }
+
+    fn transform(transformer: &ProcessTransformer) -> (TransformerResult, Asset) {
+        return transform_content(transformer, "code");
+    }
+
+    fn transform_content(transformer: &ProcessTransformer, content: &str) -> (TransformerResult, Asset) {
+        let env = Environment::new(EnvironmentContext::Browser, BuildMode::Development);
+        let key = AssetKey {
+            file_path: PathBuf::from("/project/index.js"),
+            query: None,
+            pipeline: None,
+            unique_key: None,
+            env: env.clone(),
+        };
+        let mut asset = Asset::new(&key);
+        asset.content = Content::from(content.to_string());
+
+        let ctx = TransformerContext::new(
+            0,
+            env,
+            AssetGraph::new(),
+            LogLevel::Info,
+            Arc::new(Resolver::new(ResolverOptions::with_project_root(PathBuf::from("/project")))),
+            Arc::new(ConfigCache::new()),
+        );
+
+        let result = transformer.transform(&ctx, &mut asset);
+        return (result, asset);
+    }
+
+    #[test]
+    fn test_process_is_reused() {
+        let transformer = transformer(REPLY, Duration::from_secs(10));
+
+        let (result, asset) = transform(&transformer);
+        assert_eq!(result.get_name(), "Continue");
+        assert_eq!(asset.content.as_bytes(), b"replaced 1");
+
+        let (_, asset) = transform(&transformer);
+        assert_eq!(asset.content.as_bytes(), b"replaced 2");
+    }
+
+    #[test]
+    fn test_timeout_restarts_process() {
+        let transformer = transformer("read line; sleep 10", Duration::from_millis(100));
+
+        let (result, asset) = transform(&transformer);
+        assert_eq!(result.err().message, "Plugin process timed out after 100 ms");
+        assert_eq!(asset.content.as_bytes(), b"code");
+        assert_eq!(transformer.processes.lock().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_crash_reports_stderr_and_restarts() {
+        let marker = std::env::temp_dir().join(format!("hypersonic-plugin-crash-{}", std::process::id()));
+        let _ = fs::remove_file(&marker);
+        let script = format!(
+            "if [ -e {marker} ]; then {REPLY}; else touch {marker}; read line; echo 'bad things' >&2; exit 3; fi",
+            marker = marker.display(),
+        );
+        let transformer = transformer(&script, Duration::from_secs(10));
+
+        let (result, _) = transform(&transformer);
+        assert_eq!(result.err().message, "Plugin process exited (exit status: 3)\n  bad things");
+
+        let (result, asset) = transform(&transformer);
+        assert_eq!(result.get_name(), "Continue");
+        assert_eq!(asset.content.as_bytes(), b"replaced 1");
+
+        let _ = fs::remove_file(&marker);
+    }
+
+    #[test]
+    fn test_closed_stdout_kills_process() {
+        let transformer = transformer("exec 1>&-; sleep 100", Duration::from_secs(30));
+
+        let start = Instant::now();
+        let (result, _) = transform(&transformer);
+        assert_eq!(result.err().message, "Plugin process closed stdout without exiting and was killed");
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert_eq!(transformer.processes.lock().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_timeout_covers_writing() {
+        // More than a pipe holds, to a process that never reads it
+        let transformer = transformer("sleep 100", Duration::from_millis(200));
+
+        let start = Instant::now();
+        let (result, _) = transform_content(&transformer, &"x".repeat(1 << 20));
+        assert_eq!(result.err().message, "Plugin process timed out after 200 ms");
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert_eq!(transformer.processes.lock().unwrap().len(), 0);
+    }
+}
@@ -1,3 +1,7 @@
+mod resolution;
 mod resolver;
+mod resolver_options;
 
+pub use crate::resolver::resolution::*;
 pub use crate::resolver::resolver::*;
+pub use crate::resolver::resolver_options::*;
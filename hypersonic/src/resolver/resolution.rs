@@ -0,0 +1,14 @@
+use std::path::PathBuf;
+
+/// What a specifier resolved to
+#[derive(Clone, Debug, PartialEq)]
+pub enum Resolution {
+    /// A file, with the query of the specifier if it had one
+    Path(PathBuf, Option<String>),
+    /// Left as an import for the runtime to load, eg node builtins in a node environment
+    External,
+    /// A module without content, eg `false` in package.json#browser or a builtin without a polyfill
+    Empty,
+    /// A module exporting a global variable, eg an alias to `{ "global": "jQuery" }` in package.json
+    Global(String),
+}
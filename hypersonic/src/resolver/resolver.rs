@@ -1,42 +1,305 @@
-use std::{path::PathBuf, env, borrow::Cow};
+use std::{path::PathBuf, borrow::Cow};
 
-use parcel_node_resolver::{CacheCow, OsFileSystem, SpecifierType, Resolution, Resolver, Cache};
+use parcel_node_resolver::{CacheCow, OsFileSystem, SpecifierType, Cache, ExportsCondition, Fields, ResolverError};
+use parcel_node_resolver::Resolution as ParcelResolution;
 
-pub fn resolve(
-    specifier: &str,
-    from: &PathBuf,
-) -> Result<PathBuf, String> {
-    let resolver = Resolver::node(
-        Cow::Owned(env::current_dir().unwrap().as_path().into()), 
-        CacheCow::Owned(Cache::new(OsFileSystem)),
-    );
+use crate::core::{BuildMode, DependencyKind, Environment};
 
-    let resolve_result = resolver.resolve(specifier, from, SpecifierType::Esm);
+use super::{Resolution, ResolverOptions};
 
-    if resolve_result.result.is_err() {
-        let msg = format!("Resolve Result {:?}", resolve_result.result.err());
-        return Err(msg);
+/// Resolves specifiers the way Parcel does: TypeScript and JSX extensions, directory
+/// index files, package.json `source`/`module`/`browser` fields (`browser` outside of node)
+/// and tsconfig `paths`.
+/// One is shared by every thread of a build, so each package.json, tsconfig and file
+/// system lookup is done once
+pub struct Resolver {
+    pub options: ResolverOptions,
+    cache: Cache<OsFileSystem>,
+}
+
+impl Resolver {
+    pub fn new(options: ResolverOptions) -> Self {
+        return Resolver {
+            options,
+            cache: Cache::new(OsFileSystem),
+        };
+    }
+
+    /// Resolves `specifier` imported by the file at `from` through a dependency of `kind`,
+    /// with `env` being the environment the dependency runs in. Node builtins are external
+    /// in node environments and replaced by their polyfill everywhere else
+    pub fn resolve(
+        &self,
+        specifier: &str,
+        from: &PathBuf,
+        kind: &DependencyKind,
+        env: &Environment,
+    ) -> Result<Resolution, String> {
+        let (resolution, query) = self.resolve_with_parcel(specifier, from, kind, env)?;
+
+        return match resolution {
+            ParcelResolution::Builtin(builtin) => self.resolve_builtin(&builtin, from, kind, env),
+            resolution => Ok(to_resolution(resolution, query)),
+        };
     }
 
-    let (resolution, _) = resolve_result.result.unwrap();
+    fn resolve_builtin(
+        &self,
+        builtin: &str,
+        from: &PathBuf,
+        kind: &DependencyKind,
+        env: &Environment,
+    ) -> Result<Resolution, String> {
+        if env.is_node() {
+            return Ok(Resolution::External);
+        }
+
+        let Some(Some(polyfill)) = self.options.polyfills.get(builtin) else {
+            return Ok(Resolution::Empty);
+        };
 
+        let result = self.resolve_with_parcel(polyfill, from, kind, env);
+        if result.is_err() {
+            return Err(format!(
+                "Cannot find {} to polyfill the node builtin {}, install it or map \"{}\" to false in \"polyfills\"\n{}",
+                polyfill,
+                builtin,
+                builtin,
+                result.err().unwrap(),
+            ));
+        }
+
+        return match result.unwrap() {
+            (ParcelResolution::Builtin(_), _) => Err(format!(
+                "The polyfill {} for the node builtin {} is a builtin itself",
+                polyfill,
+                builtin,
+            )),
+            (resolution, query) => Ok(to_resolution(resolution, query)),
+        };
+    }
+
+    fn resolve_with_parcel(
+        &self,
+        specifier: &str,
+        from: &PathBuf,
+        kind: &DependencyKind,
+        env: &Environment,
+    ) -> Result<(ParcelResolution, Option<String>), String> {
+        // The parcel resolver only borrows the cache, so it is cheap to create per call
+        let mut resolver = parcel_node_resolver::Resolver::parcel(
+            Cow::Borrowed(self.options.project_root.as_path()),
+            CacheCow::Borrowed(&self.cache),
+        );
+        resolver.conditions = get_conditions(env);
+        // package.json#browser swaps in code meant for browsers, which breaks node builds
+        if env.is_node() {
+            resolver.entries = Fields::MAIN | Fields::SOURCE | Fields::MODULE;
+        }
+
+        let resolve_result = resolver.resolve(specifier, from, get_specifier_type(kind));
+
+        if resolve_result.result.is_err() {
+            return Err(describe_error(&resolve_result.result.err().unwrap()));
+        }
+        return Ok(resolve_result.result.unwrap());
+    }
+}
+
+/// `require()` follows CommonJS rules, URLs and workers are relative unless they are
+/// a package with `npm:`, everything else is an ES module import
+fn get_specifier_type(kind: &DependencyKind) -> SpecifierType {
+    return match kind {
+        DependencyKind::Require => SpecifierType::Cjs,
+        DependencyKind::Url => SpecifierType::Url,
+        DependencyKind::WebWorker => SpecifierType::Url,
+        DependencyKind::ServiceWorker => SpecifierType::Url,
+        _ => SpecifierType::Esm,
+    };
+}
+
+/// Conditions matched against package.json `exports` and `imports` for the environment.
+/// The resolver adds `import` or `require` itself, from the specifier type
+fn get_conditions(env: &Environment) -> ExportsCondition {
+    let mut conditions = ExportsCondition::empty();
+    if env.is_browser() {
+        conditions |= ExportsCondition::BROWSER;
+    }
+    if env.is_worker() {
+        conditions |= ExportsCondition::WORKER;
+    }
+    if env.is_node() {
+        conditions |= ExportsCondition::NODE;
+    }
+    conditions |= match env.mode {
+        BuildMode::Development => ExportsCondition::DEVELOPMENT,
+        BuildMode::Production => ExportsCondition::PRODUCTION,
+    };
+    return conditions;
+}
+
+/// Builtins are handled before this, as they depend on the environment
+fn to_resolution(resolution: ParcelResolution, query: Option<String>) -> Resolution {
     return match resolution {
-        Resolution::Path(p) => Ok(p),
-        Resolution::Builtin(b) => {
-            let msg = format!("Resolution::Builtin {:?}", b);
-            return Err(msg);
-        },
-        Resolution::External => {
-            let msg = format!("Resolution::External");
-            return Err(msg);
-        },
-        Resolution::Empty => {
-            let msg = format!("Resolution::Empty");
-            return Err(msg);
-        },
-        Resolution::Global(s) => {
-            let msg = format!("Resolution::Global {:?}", s);
-            return Err(msg);
-        },
+        ParcelResolution::Path(p) => Resolution::Path(p, query),
+        ParcelResolution::Builtin(_) => Resolution::External,
+        ParcelResolution::External => Resolution::External,
+        ParcelResolution::Empty => Resolution::Empty,
+        ParcelResolution::Global(global) => Resolution::Global(global),
+    };
+}
+
+fn describe_error(error: &ResolverError) -> String {
+    return match error {
+        ResolverError::FileNotFound { relative, from } => format!(
+            "Cannot find file {} from {}",
+            relative.display(),
+            from.display(),
+        ),
+        ResolverError::ModuleNotFound { module } => format!("Cannot find module {}", module),
+        ResolverError::ModuleEntryNotFound { module, entry_path, field, .. } => format!(
+            "Cannot find the \"{}\" entry of module {}: {}",
+            field,
+            module,
+            entry_path.display(),
+        ),
+        ResolverError::ModuleSubpathNotFound { module, path, .. } => format!(
+            "Cannot find {} in module {}",
+            path.display(),
+            module,
+        ),
+        error => format!("{:?}", error),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::core::{BuildMode, EnvironmentContext};
+
+    use super::*;
+
+    fn fixture(path: &str) -> PathBuf {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../fixtures").canonicalize().unwrap();
+        return root.join(path);
+    }
+
+    fn resolver(project: &str) -> Resolver {
+        return Resolver::new(ResolverOptions::with_project_root(fixture(project)));
+    }
+
+    fn browser() -> Environment {
+        return Environment::new(EnvironmentContext::Browser, BuildMode::Development);
+    }
+
+    #[test]
+    fn test_typescript_extensions_and_index_files() {
+        let resolver = resolver("resolve");
+        let from = fixture("resolve/src/index.ts");
+        assert_eq!(
+            resolver.resolve("./utils", &from, &DependencyKind::Import, &browser()).unwrap(),
+            Resolution::Path(fixture("resolve/src/utils.ts"), None),
+        );
+        assert_eq!(
+            resolver.resolve("./components", &from, &DependencyKind::Import, &browser()).unwrap(),
+            Resolution::Path(fixture("resolve/src/components/index.tsx"), None),
+        );
+    }
+
+    #[test]
+    fn test_tsconfig_paths() {
+        let resolution = resolver("resolve").resolve("@/components", &fixture("resolve/src/index.ts"), &DependencyKind::Import, &browser());
+        assert_eq!(resolution.unwrap(), Resolution::Path(fixture("resolve/src/components/index.tsx"), None));
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let err = resolver("resolve").resolve("./missing", &fixture("resolve/src/index.ts"), &DependencyKind::Import, &browser()).err().unwrap();
+        assert!(err.starts_with("Cannot find file missing from "), "{}", err);
+    }
+
+    #[test]
+    fn test_builtins() {
+        let resolver = resolver("builtins");
+        let from = fixture("builtins/index.js");
+        let node = Environment::new(EnvironmentContext::Node, BuildMode::Development);
+
+        assert_eq!(resolver.resolve("fs", &from, &DependencyKind::Import, &node).unwrap(), Resolution::External);
+        assert_eq!(resolver.resolve("node:buffer", &from, &DependencyKind::Import, &node).unwrap(), Resolution::External);
+        assert_eq!(resolver.resolve("fs", &from, &DependencyKind::Import, &browser()).unwrap(), Resolution::Empty);
+        assert_eq!(
+            resolver.resolve("node:buffer", &from, &DependencyKind::Import, &browser()).unwrap(),
+            Resolution::Path(fixture("builtins/node_modules/buffer/index.js"), None),
+        );
+
+        let err = resolver.resolve("path", &from, &DependencyKind::Import, &browser()).err().unwrap();
+        assert!(err.starts_with("Cannot find path-browserify to polyfill the node builtin path"), "{}", err);
+    }
+
+    #[test]
+    fn test_configured_polyfills() {
+        let mut options = ResolverOptions::with_project_root(fixture("builtins"));
+        options.polyfills.insert(String::from("buffer"), None);
+        options.polyfills.insert(String::from("path"), Some(String::from("./path.js")));
+        let resolver = Resolver::new(options);
+        let from = fixture("builtins/index.js");
+
+        assert_eq!(resolver.resolve("buffer", &from, &DependencyKind::Import, &browser()).unwrap(), Resolution::Empty);
+        assert_eq!(resolver.resolve("path", &from, &DependencyKind::Import, &browser()).unwrap(), Resolution::Path(fixture("builtins/path.js"), None));
+    }
+
+    #[test]
+    fn test_package_json_aliases() {
+        let resolver = resolver("builtins");
+        let from = fixture("builtins/index.js");
+        assert_eq!(resolver.resolve("jquery", &from, &DependencyKind::Import, &browser()).unwrap(), Resolution::Global(String::from("jQuery")));
+        assert_eq!(resolver.resolve("./server.js", &from, &DependencyKind::Import, &browser()).unwrap(), Resolution::Empty);
+    }
+
+    #[test]
+    fn test_specifier_types() {
+        let resolver = resolver("exports");
+        let from = fixture("exports/index.js");
+
+        // URLs are relative, imports of the same specifier look for a package
+        assert_eq!(
+            resolver.resolve("b.js", &from, &DependencyKind::Url, &browser()).unwrap(),
+            Resolution::Path(fixture("exports/b.js"), None),
+        );
+        assert_eq!(
+            resolver.resolve("b.js", &from, &DependencyKind::WebWorker, &browser()).unwrap(),
+            Resolution::Path(fixture("exports/b.js"), None),
+        );
+        assert!(resolver.resolve("b.js", &from, &DependencyKind::Import, &browser()).is_err());
+    }
+
+    #[test]
+    fn test_exports_conditions() {
+        let resolver = resolver("exports");
+        let from = fixture("exports/index.js");
+        let pkg = |file: &str| Resolution::Path(fixture("exports/node_modules/pkg").join(file), None);
+
+        let production = Environment::new(EnvironmentContext::Browser, BuildMode::Production);
+        assert_eq!(resolver.resolve("pkg", &from, &DependencyKind::Import, &production).unwrap(), pkg("import.js"));
+        assert_eq!(resolver.resolve("pkg", &from, &DependencyKind::Require, &production).unwrap(), pkg("require.js"));
+        assert_eq!(resolver.resolve("pkg", &from, &DependencyKind::Import, &browser()).unwrap(), pkg("development.js"));
+
+        let worker = Environment::new(EnvironmentContext::WebWorker, BuildMode::Production);
+        assert_eq!(resolver.resolve("pkg", &from, &DependencyKind::Import, &worker).unwrap(), pkg("worker.js"));
+
+        let node = Environment::new(EnvironmentContext::Node, BuildMode::Production);
+        assert_eq!(resolver.resolve("pkg", &from, &DependencyKind::Require, &node).unwrap(), pkg("node.js"));
+    }
+
+    #[test]
+    fn test_browser_field_is_ignored_for_node() {
+        let resolver = resolver("exports");
+        let from = fixture("exports/index.js");
+        let fields = |file: &str| Resolution::Path(fixture("exports/node_modules/fields").join(file), None);
+
+        let node = Environment::new(EnvironmentContext::Node, BuildMode::Development);
+        assert_eq!(resolver.resolve("fields", &from, &DependencyKind::Import, &browser()).unwrap(), fields("browser.js"));
+        assert_eq!(resolver.resolve("fields", &from, &DependencyKind::Import, &node).unwrap(), fields("main.js"));
     }
 }
@@ -0,0 +1,53 @@
+use std::{collections::HashMap, env, path::PathBuf};
+
+/// Browser polyfills for node builtins, the packages Parcel uses
+pub const DEFAULT_POLYFILLS: &[(&str, &str)] = &[
+    ("assert", "assert/"),
+    ("buffer", "buffer/"),
+    ("console", "console-browserify"),
+    ("constants", "constants-browserify"),
+    ("crypto", "crypto-browserify"),
+    ("domain", "domain-browser"),
+    ("events", "events/"),
+    ("http", "stream-http"),
+    ("https", "https-browserify"),
+    ("os", "os-browserify"),
+    ("path", "path-browserify"),
+    ("process", "process/"),
+    ("punycode", "punycode/"),
+    ("querystring", "querystring-es3"),
+    ("stream", "stream-browserify"),
+    ("string_decoder", "string_decoder/"),
+    ("sys", "util/"),
+    ("timers", "timers-browserify"),
+    ("tty", "tty-browserify"),
+    ("url", "url/"),
+    ("util", "util/"),
+    ("vm", "vm-browserify"),
+    ("zlib", "browserify-zlib"),
+];
+
+#[derive(Clone, Debug)]
+pub struct ResolverOptions {
+    /// Root used for absolute specifiers and as the upper bound for lookups
+    pub project_root: PathBuf,
+    /// Node builtin -> package replacing it outside of node, resolved from the importing file.
+    /// Builtins mapped to None or missing from the map become empty modules
+    pub polyfills: HashMap<String, Option<String>>,
+}
+
+impl ResolverOptions {
+    pub fn new() -> Self {
+        return ResolverOptions::with_project_root(env::current_dir().unwrap());
+    }
+
+    pub fn with_project_root(project_root: PathBuf) -> Self {
+        return ResolverOptions {
+            project_root,
+            polyfills: DEFAULT_POLYFILLS
+                .iter()
+                .map(|(builtin, polyfill)| (builtin.to_string(), Some(polyfill.to_string())))
+                .collect(),
+        };
+    }
+}
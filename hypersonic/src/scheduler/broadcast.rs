@@ -0,0 +1,63 @@
+use std::ops::IndexMut;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::utils::Queue;
+
+use super::{Scheduler, Spawner};
+
+/// A single shared queue, every push wakes up every worker
+pub struct BroadcastScheduler {
+    threads: usize,
+}
+
+impl BroadcastScheduler {
+    pub fn new(threads: usize) -> Self {
+        return BroadcastScheduler { threads };
+    }
+}
+
+struct BroadcastSpawner<'a, T> {
+    queue: &'a Queue<T>,
+    pending: &'a AtomicUsize,
+}
+
+impl<'a, T> Spawner<T> for BroadcastSpawner<'a, T> {
+    fn spawn(&self, value: T) {
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        self.queue.push(value);
+    }
+}
+
+impl<T: Send> Scheduler<T> for BroadcastScheduler {
+    fn run(&self, seed: Vec<T>, handler: &(dyn Fn(usize, T, &dyn Spawner<T>) + Sync)) {
+        if seed.len() == 0 {
+            return;
+        }
+
+        let (queue, mut receivers) = Queue::<T>::new(self.threads);
+        let pending = AtomicUsize::new(seed.len());
+        for value in seed {
+            queue.push(value);
+        }
+
+        thread::scope(|scope| {
+            for t in 0..self.threads {
+                let receiver = receivers.index_mut(t).take().unwrap();
+                let queue = &queue;
+                let pending = &pending;
+
+                scope.spawn(move || {
+                    let spawner = BroadcastSpawner { queue, pending };
+
+                    while let Some(value) = queue.recv(&receiver) {
+                        handler(t, value, &spawner);
+                        if pending.fetch_sub(1, Ordering::SeqCst) == 1 {
+                            queue.disconnect_all();
+                        }
+                    }
+                });
+            }
+        });
+    }
+}
@@ -0,0 +1,65 @@
+mod broadcast;
+mod rayon_scope;
+mod scheduler;
+mod single_threaded;
+mod work_stealing;
+
+pub use crate::scheduler::broadcast::*;
+pub use crate::scheduler::rayon_scope::*;
+pub use crate::scheduler::scheduler::*;
+pub use crate::scheduler::single_threaded::*;
+pub use crate::scheduler::work_stealing::*;
+
+use crate::platform::SchedulerKind;
+
+pub fn create_scheduler<T: Send>(kind: &SchedulerKind, threads: usize) -> Box<dyn Scheduler<T>> {
+    return match kind {
+        SchedulerKind::Broadcast => Box::new(BroadcastScheduler::new(threads)),
+        SchedulerKind::WorkStealing => Box::new(WorkStealingScheduler::new(threads)),
+        SchedulerKind::Rayon => Box::new(RayonScheduler::new(threads)),
+        SchedulerKind::SingleThreaded => Box::new(SingleThreadedScheduler::new()),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    fn count_tree(scheduler: &dyn Scheduler<usize>) -> usize {
+        let handled = AtomicUsize::new(0);
+        scheduler.run(vec![8, 3], &|_, value, spawner| {
+            for child in 0..value {
+                spawner.spawn(child);
+            }
+            handled.fetch_add(1, Ordering::SeqCst);
+        });
+        return handled.load(Ordering::SeqCst);
+    }
+
+    #[test]
+    fn test_all_schedulers_drain() {
+        for kind in [
+            SchedulerKind::Broadcast,
+            SchedulerKind::WorkStealing,
+            SchedulerKind::Rayon,
+            SchedulerKind::SingleThreaded,
+        ] {
+            let scheduler = create_scheduler::<usize>(&kind, 4);
+            assert_eq!(count_tree(scheduler.as_ref()), (1 << 8) + (1 << 3), "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn test_single_threaded_order() {
+        let order = std::sync::Mutex::new(Vec::new());
+        SingleThreadedScheduler::new().run(vec![2], &|_, value, spawner| {
+            order.lock().unwrap().push(value);
+            for child in 0..value {
+                spawner.spawn(child);
+            }
+        });
+        assert_eq!(*order.lock().unwrap(), vec![2, 0, 1, 0]);
+    }
+}
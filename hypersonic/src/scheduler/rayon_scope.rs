@@ -0,0 +1,50 @@
+use rayon::{Scope, ThreadPool, ThreadPoolBuilder};
+
+use super::{Scheduler, Spawner};
+
+/// Every item becomes a task spawned into a rayon scope
+pub struct RayonScheduler {
+    pool: ThreadPool,
+}
+
+impl RayonScheduler {
+    pub fn new(threads: usize) -> Self {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .expect("Unable to create rayon thread pool");
+        return RayonScheduler { pool };
+    }
+}
+
+struct ScopeSpawner<'a, 'scope, T> {
+    scope: &'a Scope<'scope>,
+    handler: &'scope (dyn Fn(usize, T, &dyn Spawner<T>) + Sync),
+}
+
+impl<'a, 'scope, T: Send + 'scope> Spawner<T> for ScopeSpawner<'a, 'scope, T> {
+    fn spawn(&self, value: T) {
+        spawn_task(self.scope, self.handler, value);
+    }
+}
+
+fn spawn_task<'scope, T: Send + 'scope>(
+    scope: &Scope<'scope>,
+    handler: &'scope (dyn Fn(usize, T, &dyn Spawner<T>) + Sync),
+    value: T,
+) {
+    scope.spawn(move |scope| {
+        let t = rayon::current_thread_index().unwrap_or(0);
+        handler(t, value, &ScopeSpawner { scope, handler });
+    });
+}
+
+impl<T: Send> Scheduler<T> for RayonScheduler {
+    fn run(&self, seed: Vec<T>, handler: &(dyn Fn(usize, T, &dyn Spawner<T>) + Sync)) {
+        self.pool.scope(|scope| {
+            for value in seed {
+                spawn_task(scope, handler, value);
+            }
+        });
+    }
+}
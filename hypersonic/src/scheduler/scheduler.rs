@@ -0,0 +1,13 @@
+/// Handle given to a handler for queueing follow-up work
+pub trait Spawner<T> {
+    fn spawn(&self, value: T);
+}
+
+/// Strategy for running a self-expanding set of work items to completion.
+///
+/// The handler receives the index of the worker it runs on, the item and a
+/// spawner for any items it produces. `run` returns once every item,
+/// including spawned ones, has been handled.
+pub trait Scheduler<T: Send> {
+    fn run(&self, seed: Vec<T>, handler: &(dyn Fn(usize, T, &dyn Spawner<T>) + Sync));
+}
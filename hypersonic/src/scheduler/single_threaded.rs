@@ -0,0 +1,40 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+use super::{Scheduler, Spawner};
+
+/// Runs every item in FIFO order on the calling thread,
+/// giving a deterministic order of actions between runs
+pub struct SingleThreadedScheduler {}
+
+impl SingleThreadedScheduler {
+    pub fn new() -> Self {
+        return SingleThreadedScheduler {};
+    }
+}
+
+struct QueueSpawner<T> {
+    queue: RefCell<VecDeque<T>>,
+}
+
+impl<T> Spawner<T> for QueueSpawner<T> {
+    fn spawn(&self, value: T) {
+        self.queue.borrow_mut().push_back(value);
+    }
+}
+
+impl<T: Send> Scheduler<T> for SingleThreadedScheduler {
+    fn run(&self, seed: Vec<T>, handler: &(dyn Fn(usize, T, &dyn Spawner<T>) + Sync)) {
+        let spawner = QueueSpawner {
+            queue: RefCell::new(VecDeque::from(seed)),
+        };
+
+        loop {
+            let value_opt = spawner.queue.borrow_mut().pop_front();
+            if value_opt.is_none() {
+                break;
+            }
+            handler(0, value_opt.unwrap(), &spawner);
+        }
+    }
+}
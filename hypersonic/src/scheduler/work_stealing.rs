@@ -0,0 +1,42 @@
+use std::thread;
+
+use crate::utils::{WorkStealingHandle, WorkStealingQueue};
+
+use super::{Scheduler, Spawner};
+
+/// Per-worker deques, idle workers steal from busy ones
+pub struct WorkStealingScheduler {
+    threads: usize,
+}
+
+impl WorkStealingScheduler {
+    pub fn new(threads: usize) -> Self {
+        return WorkStealingScheduler { threads };
+    }
+}
+
+impl<T> Spawner<T> for WorkStealingHandle<T> {
+    fn spawn(&self, value: T) {
+        self.push(value);
+    }
+}
+
+impl<T: Send> Scheduler<T> for WorkStealingScheduler {
+    fn run(&self, seed: Vec<T>, handler: &(dyn Fn(usize, T, &dyn Spawner<T>) + Sync)) {
+        let (queue, workers) = WorkStealingQueue::<T>::new(self.threads);
+        for value in seed {
+            queue.push(value);
+        }
+
+        thread::scope(|scope| {
+            for worker in workers {
+                scope.spawn(move || {
+                    while let Some(value) = worker.next() {
+                        handler(worker.index(), value, &worker);
+                        worker.complete();
+                    }
+                });
+            }
+        });
+    }
+}
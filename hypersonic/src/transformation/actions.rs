@@ -4,9 +4,9 @@ use std::path::PathBuf;
 #[derive(Clone)]
 pub enum Action {
   EntryAsset(PathBuf),
-  CreateAsset(PathBuf),
+  /// Creates (or reuses) the asset a dependency resolved to
+  CreateAsset(usize),
   ReadContents(usize),
   AssignTransformers(usize),
   TransformContents(usize, usize),
-  Done(usize),
 }
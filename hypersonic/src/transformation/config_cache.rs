@@ -0,0 +1,183 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use json_comments::{CommentSettings, strip_comments_in_place};
+use serde_json::Value;
+
+use crate::core::Invalidation;
+
+/// A config file found by `TransformerContext::load_config`
+#[derive(Clone, Debug)]
+pub struct LoadedConfig {
+    pub file_path: PathBuf,
+    pub contents: Arc<Value>,
+}
+
+/// Config files read during a build, shared between threads so each is read once.
+/// Files that don't exist are remembered too, as every asset below them looks for them
+pub struct ConfigCache {
+    files: DashMap<PathBuf, Result<Option<Arc<Value>>, String>>,
+}
+
+impl ConfigCache {
+    pub fn new() -> Self {
+        return ConfigCache {
+            files: DashMap::new(),
+        };
+    }
+
+    /// Looks for each of `file_names` in `from`'s directory, then its parents up to
+    /// `project_root`, and returns the first one found. Every path looked at is added
+    /// to `invalidations`
+    pub fn load(
+        &self,
+        from: &Path,
+        file_names: &[&str],
+        project_root: &Path,
+        invalidations: &mut Vec<Invalidation>,
+    ) -> Result<Option<LoadedConfig>, String> {
+        let mut dir = from.parent();
+
+        while let Some(current) = dir {
+            for file_name in file_names {
+                let file_path = current.join(file_name);
+                let contents = self.read(&file_path);
+                if let Ok(None) = contents {
+                    invalidations.push(Invalidation::FileCreate(file_path));
+                    continue;
+                }
+
+                invalidations.push(Invalidation::FileChange(file_path.clone()));
+                let contents = contents?.unwrap();
+                return Ok(Some(LoadedConfig { file_path, contents }));
+            }
+
+            if current == project_root {
+                break;
+            }
+            dir = current.parent();
+        }
+
+        return Ok(None);
+    }
+
+    /// Parsed contents of a JSON file with comments, None when there is no such file
+    pub fn read(&self, file_path: &Path) -> Result<Option<Arc<Value>>, String> {
+        // The entry is held while reading so concurrent lookups don't read the file twice
+        return self.files
+            .entry(file_path.to_path_buf())
+            .or_insert_with(|| read_config(file_path))
+            .clone();
+    }
+}
+
+fn read_config(file_path: &Path) -> Result<Option<Arc<Value>>, String> {
+    if !file_path.is_file() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(file_path);
+    if content.is_err() {
+        return Err(format!("Unable to read {}: {}", file_path.display(), content.err().unwrap()));
+    }
+    let mut content = content.unwrap();
+
+    // Comments are replaced with whitespace so parse errors keep their line and column
+    if strip_comments_in_place(&mut content, CommentSettings::c_style(), true).is_err() {
+        return Err(format!("Unterminated comment in {}", file_path.display()));
+    }
+
+    let value: Result<Value, _> = serde_json::from_str(&content);
+    if value.is_err() {
+        return Err(format!("Invalid JSON in {}: {}", file_path.display(), value.err().unwrap()));
+    }
+    return Ok(Some(Arc::new(value.unwrap())));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(path: &str) -> PathBuf {
+        return Path::new(env!("CARGO_MANIFEST_DIR")).join("../fixtures/load-config").join(path);
+    }
+
+    #[test]
+    fn test_closest_config_wins() {
+        let cache = ConfigCache::new();
+        let mut invalidations = Vec::new();
+        let config = cache.load(
+            &fixture("src/nested/index.ts"),
+            &["tsconfig.json"],
+            &fixture(""),
+            &mut invalidations,
+        ).unwrap().unwrap();
+
+        assert_eq!(config.file_path, fixture("tsconfig.json"));
+        assert_eq!(config.contents["compilerOptions"]["jsx"], "react-jsx");
+        assert_eq!(invalidations, vec![
+            Invalidation::FileCreate(fixture("src/nested/tsconfig.json")),
+            Invalidation::FileCreate(fixture("src/tsconfig.json")),
+            Invalidation::FileChange(fixture("tsconfig.json")),
+        ]);
+    }
+
+    #[test]
+    fn test_file_names_are_tried_per_directory() {
+        let cache = ConfigCache::new();
+        let mut invalidations = Vec::new();
+        let config = cache.load(
+            &fixture("src/nested/index.ts"),
+            &["tsconfig.json", "package.json"],
+            &fixture(""),
+            &mut invalidations,
+        ).unwrap().unwrap();
+
+        assert_eq!(config.file_path, fixture("src/package.json"));
+        assert_eq!(config.contents["browserslist"], "> 0.5%");
+        assert_eq!(invalidations.len(), 4);
+    }
+
+    #[test]
+    fn test_search_stops_at_project_root() {
+        let cache = ConfigCache::new();
+        let mut invalidations = Vec::new();
+        let config = cache.load(
+            &fixture("src/nested/index.ts"),
+            &["tsconfig.json"],
+            &fixture("src"),
+            &mut invalidations,
+        ).unwrap();
+
+        assert!(config.is_none());
+        assert_eq!(invalidations, vec![
+            Invalidation::FileCreate(fixture("src/nested/tsconfig.json")),
+            Invalidation::FileCreate(fixture("src/tsconfig.json")),
+        ]);
+    }
+
+    #[test]
+    fn test_files_are_read_once() {
+        let cache = ConfigCache::new();
+        let first = cache.read(&fixture("tsconfig.json")).unwrap().unwrap();
+        let second = cache.read(&fixture("tsconfig.json")).unwrap().unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let cache = ConfigCache::new();
+        let mut invalidations = Vec::new();
+        let err = cache.load(
+            &fixture("invalid/index.js"),
+            &["config.json"],
+            &fixture(""),
+            &mut invalidations,
+        ).err().unwrap();
+
+        assert!(err.starts_with("Invalid JSON in "));
+        assert_eq!(invalidations, vec![Invalidation::FileChange(fixture("invalid/config.json"))]);
+    }
+}
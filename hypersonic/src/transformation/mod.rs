@@ -1,13 +1,17 @@
 mod actions;
+mod config_cache;
 mod transform;
 mod transformer;
 mod transformer_context;
 mod transformer_container;
+mod transformer_registry;
 mod transformer_result;
 
 pub use crate::transformation::actions::*;
+pub use crate::transformation::config_cache::*;
 pub use crate::transformation::transform::*;
 pub use crate::transformation::transformer::*;
 pub use crate::transformation::transformer_context::*;
 pub use crate::transformation::transformer_container::*;
+pub use crate::transformation::transformer_registry::*;
 pub use crate::transformation::transformer_result::*;
@@ -1,200 +1,374 @@
-use std::fs;
-use std::ops::IndexMut;
-use std::sync::Arc;
-use std::thread::{self, JoinHandle};
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 
 use dashmap::DashMap;
+use dashmap::mapref::entry::Entry;
 
-use crate::{core::Asset, utils::StandardProfiler};
-use crate::platform::Args;
-use crate::utils::{StaticContainer, hash_path_buff_sha_256};
-use crate::utils::Queue;
-use crate::default_plugins::{DefaultHTMLTransformer, DefaultJSTransformer, DefaultNoopTransformer};
+use crate::bundler::{BuildOptions, Reporter, ReporterEvent};
+use crate::core::{Asset, AssetGraph, AssetKey, BuildError, BuildStage, Content, Dependency, DependencyKind, Environment, EnvironmentContext, InlineAsset, Severity, compose_source_maps};
+use crate::resolver::Resolver;
+use crate::utils::StandardProfiler;
+use crate::utils::{SegmentedContainer, canonicalize_path};
+use crate::scheduler::{Spawner, create_scheduler};
 
-use super::{TransformerContainer, TransformerContext};
+use super::{ConfigCache, TransformerContainer, TransformerContext, TransformerError, TransformerResult};
 use super::actions::Action;
 
 pub fn transform(
-    args: &Args,
-    assets: Arc<StaticContainer<Asset>>,
-    assets_index: Arc<DashMap<String, usize>>,
+    options: &BuildOptions,
+    transformers: &TransformerContainer,
+    reporters: &[Box<dyn Reporter>],
+    assets: Arc<SegmentedContainer<Asset>>,
+    assets_index: Arc<DashMap<AssetKey, usize>>,
+    graph: Arc<AssetGraph>,
     profiler: &StandardProfiler,
-) {
-    let mut handles = Vec::<JoinHandle<()>>::new();
-    
-    let (queue, mut receivers) = Queue::<Action>::new(args.threads);
-    let in_pipeline = Arc::new(AtomicUsize::new(0));
+) -> Vec<BuildError> {
+    let diagnostics = Arc::new(Mutex::new(Vec::<BuildError>::new()));
 
-    // Entry asset
-    queue.push(Action::EntryAsset(args.entry.clone()));
-    in_pipeline.fetch_add(1, Ordering::Acquire);
+    let pipeline = Pipeline {
+        options: options.clone(),
+        resolver: Arc::new(Resolver::new(options.resolver.clone())),
+        config_cache: Arc::new(ConfigCache::new()),
+        assets,
+        assets_index,
+        graph,
+        profiler: profiler.clone(),
+        diagnostics: diagnostics.clone(),
+        transformers,
+        reporters,
+    };
+
+    let scheduler = create_scheduler::<Action>(&options.scheduler, options.threads);
 
     let profiler_end_transformations = profiler.start();
 
-    for t in 0..args.threads {
-        let args = args.clone();
-        let assets = assets.clone();
-        let assets_index = assets_index.clone();
-        let queue = queue.clone();
-        let in_pipeline = in_pipeline.clone();
-        let receiver = receivers.index_mut(t).take().unwrap();
-        let profiler = profiler.clone();
-
-        handles.push(thread::spawn(move || {
-            let mut transformers = TransformerContainer::new();
-
-            transformers.add("*.html", Box::new(DefaultHTMLTransformer::new()));
-            transformers.add("*.js", Box::new(DefaultJSTransformer::new(false, false)));
-            transformers.add("*.jsx", Box::new(DefaultJSTransformer::new(true, false)));
-            transformers.add("*.ts", Box::new(DefaultJSTransformer::new(false, true)));
-            transformers.add("*.tsx", Box::new(DefaultJSTransformer::new(true, true)));
-            transformers.add("*.css", Box::new(DefaultNoopTransformer::new()));
-
-            // let mut hits = 0;
-            loop {
-                if in_pipeline.load(Ordering::Acquire) == 0 {
-                    queue.disconnect_all();
-                    break;
-                }
-
-                let action_opt = queue.recv(&receiver);
-                if action_opt.is_none() {
-                    break;
-                }
-                let action = action_opt.unwrap();
-
-                if args.log_level.is_verbose() {
-                    println!("T{}: {:?}", t, action);
-                }
-
-                let profiler_end = profiler.start();
-
-                match action {
-                    Action::EntryAsset(file_path) => {
-                        let hash = hash_path_buff_sha_256(&file_path);
-                        let id = assets.push(Asset {
-                            file_path,
-                            transformer_pattern: String::from(""),
-                            content: String::from(""),
-                            content_hash: String::from(""),
-                        });
-                        assets_index.insert(hash, id);
-                        if args.profiling {
-                            profiler_end("CreateAsset");
-                        }
-                        queue.push(Action::ReadContents(id));
-                    },
-                    Action::CreateAsset(file_path) => {
-                        let hash = hash_path_buff_sha_256(&file_path);
-                        if assets_index.contains_key(&hash) {
-                            continue;
-                        }
-                        in_pipeline.fetch_add(1, Ordering::Acquire);
-
-                        let id = assets.push(Asset {
-                            file_path,
-                            transformer_pattern: String::from(""),
-                            content: String::from(""),
-                            content_hash: String::from(""),
-                        });
-                        assets_index.insert(hash, id);
-
-                        if args.profiling {
-                            profiler_end("CreateAsset");
-                        }
-                        queue.push(Action::ReadContents(id));
-                    }
-                    Action::ReadContents(id) => {
-                        let asset_container = assets.index(id);
-                        let container_result = asset_container.lock();
-                        let mut container = container_result.unwrap();
-                        let asset = container.get_value_mut().unwrap();
-
-                        let content = fs::read_to_string(&asset.file_path);
-                        if content.is_err() {
-                            panic!("Unable to read file: {:?}", &asset.file_path);
-                        }
+    let entries = options.entries
+        .iter()
+        .map(|entry| Action::EntryAsset(entry.clone()))
+        .collect();
 
-                        asset.content = content.unwrap();
+    scheduler.run(
+        entries,
+        &|t, action, queue| {
+            for reporter in reporters {
+                reporter.report(&ReporterEvent::Action(t, &action));
+            }
+            pipeline.run(action, queue);
+        },
+    );
 
-                        if args.profiling {
-                            profiler_end("ReadContents");
-                        }
-                        queue.push(Action::AssignTransformers(id));
-                    }
-                    Action::AssignTransformers(id) => {
-                        let asset_container = assets.index(id);
-                        let container_result = asset_container.lock();
-                        let mut container = container_result.unwrap();
-                        let asset = container.get_value_mut().unwrap();
-
-                        let pattern_result = transformers.match_pattern(&asset.file_path);
-                        if pattern_result.is_err() {
-                            panic!("No transformers match {:?}", &asset.file_path.file_name().unwrap());
-                        }
+    profiler_end_transformations("Transformation");
 
-                        asset.transformer_pattern = pattern_result.unwrap();
+    return diagnostics.lock().unwrap().drain(..).collect();
+}
 
-                        if args.profiling {
-                            profiler_end("AssignTransformers");
-                        }
-                        queue.push(Action::TransformContents(id, 0));
+/// Upper bound on how many times one asset can change type
+const MAX_TYPE_CHANGES: usize = 8;
+
+/// State shared by every worker running pipeline actions
+struct Pipeline<'a> {
+    options: BuildOptions,
+    resolver: Arc<Resolver>,
+    config_cache: Arc<ConfigCache>,
+    assets: Arc<SegmentedContainer<Asset>>,
+    assets_index: Arc<DashMap<AssetKey, usize>>,
+    graph: Arc<AssetGraph>,
+    profiler: StandardProfiler,
+    diagnostics: Arc<Mutex<Vec<BuildError>>>,
+    transformers: &'a TransformerContainer,
+    reporters: &'a [Box<dyn Reporter>],
+}
+
+impl<'a> Pipeline<'a> {
+    fn run(&self, action: Action, queue: &dyn Spawner<Action>) {
+        let profiler_end = self.profiler.start();
+
+        match action {
+            Action::EntryAsset(file_path) => {
+                let specifier = file_path.to_str().unwrap().to_string();
+                let dependency_id = self.graph.add_entry(Dependency::new(
+                    &specifier,
+                    DependencyKind::Entry,
+                    file_path,
+                ));
+                self.create_asset(dependency_id, None, queue);
+                if self.options.profiling {
+                    profiler_end("CreateAsset");
+                }
+            },
+            Action::CreateAsset(dependency_id) => {
+                self.create_asset(dependency_id, None, queue);
+                if self.options.profiling {
+                    profiler_end("CreateAsset");
+                }
+            }
+            Action::ReadContents(id) => {
+                let mut asset = self.assets.claim(id).unwrap();
+
+                let content = Content::read(&asset.file_path);
+                if content.is_err() {
+                    self.diagnostics.lock().unwrap().push(BuildError::new(
+                        &asset.file_path,
+                        BuildStage::ReadContents,
+                        &format!("Unable to read file: {}", content.err().unwrap()),
+                    ));
+                    return;
+                }
+
+                asset.content = content.unwrap();
+
+                if self.options.profiling {
+                    profiler_end("ReadContents");
+                }
+                queue.spawn(Action::AssignTransformers(id));
+            }
+            Action::AssignTransformers(id) => {
+                let mut asset = self.assets.claim(id).unwrap();
+
+                let pattern_result = self.transformers.match_pattern(
+                    &asset.file_path.with_extension(&asset.asset_type),
+                    &self.options.resolver.project_root,
+                    asset.pipeline.as_deref(),
+                );
+                if pattern_result.is_err() {
+                    self.diagnostics.lock().unwrap().push(BuildError::new(
+                        &asset.file_path,
+                        BuildStage::AssignTransformers,
+                        &pattern_result.err().unwrap(),
+                    ));
+                    return;
+                }
+
+                let pattern_match = pattern_result.unwrap();
+                for reporter in self.reporters {
+                    reporter.report(&ReporterEvent::TransformersMatched(id, &pattern_match));
+                }
+                asset.transformer_pattern = pattern_match.pattern;
+
+                if self.options.profiling {
+                    profiler_end("AssignTransformers");
+                }
+                queue.spawn(Action::TransformContents(id, 0));
+            }
+            Action::TransformContents(id, index) => {
+                let mut asset = self.assets.claim(id).unwrap();
+
+                let transformer_opt = self.transformers.index(&asset.transformer_pattern, index);
+                if transformer_opt.is_none() {
+                    return;
+                }
+                let transformer = transformer_opt.unwrap();
+
+                let ctx = TransformerContext::new(
+                    id,
+                    asset.env.clone(),
+                    self.graph.clone(),
+                    self.options.log_level.clone(),
+                    self.resolver.clone(),
+                    self.config_cache.clone(),
+                );
+
+                let asset_type = asset.asset_type.clone();
+                let result = transformer.transform(&ctx, &mut asset);
+                for reporter in self.reporters {
+                    reporter.report(&ReporterEvent::TransformerResult(id, &transformer.get_name(), result.get_name()));
+                }
+
+                for invalidation in ctx.take_invalidations() {
+                    if !asset.invalidations.contains(&invalidation) {
+                        asset.invalidations.push(invalidation);
                     }
-                    Action::TransformContents(id, index) => {
-                        let asset_container = assets.index(id);
-                        let container_result = asset_container.lock();
-                        let mut container = container_result.unwrap();
-                        let asset = container.get_value_mut().unwrap();
-
-                        let transformer_opt = transformers.index(&asset.transformer_pattern, index);
-                        if transformer_opt.is_none() {
-                            queue.push(Action::Done(id));
-                            continue;
-                        }
-                        let transformer = transformer_opt.unwrap();
+                }
+
+                for (severity, diagnostic) in ctx.take_diagnostics() {
+                    self.add_transformer_diagnostic(&mut asset, &transformer.get_name(), severity, diagnostic);
+                }
 
-                        let ctx = Box::new(TransformerContext {
-                            log_level: args.log_level.clone(),
-                            queue: queue.clone(),
-                        });
+                // Children are only added once the result is kept, so skipping or failing drops them
+                let dependencies = ctx.take_dependencies();
+                let inline_assets = ctx.take_inline_assets();
 
-                        let result = transformer.transform(&ctx, asset);
-                        if result.is_err() {
-                            let err = result.err();
-                            panic!("Error at: {}\n{}", transformer.get_name(), err);
-                        }
+                if let TransformerResult::Skip = result {
+                    queue.spawn(Action::TransformContents(id, index + 1));
+                    return;
+                }
 
-                        if args.profiling {
-                            profiler_end(&format!("TransformContents - {}", transformer.get_name()));
+                match result {
+                    TransformerResult::Continue | TransformerResult::Skip => {}
+                    TransformerResult::Break => {
+                        self.add_children(dependencies, inline_assets, queue);
+                        return;
+                    }
+                    TransformerResult::Replace(replacement) => {
+                        if let Some(map) = replacement.map {
+                            let composed = compose_source_maps(asset.map.take(), map);
+                            if composed.is_err() {
+                                let mut error = BuildError::new(
+                                    &asset.file_path,
+                                    BuildStage::TransformContents,
+                                    &composed.err().unwrap(),
+                                );
+                                error.transformer = Some(transformer.get_name());
+                                self.diagnostics.lock().unwrap().push(error);
+                                return;
+                            }
+                            asset.map = Some(composed.unwrap());
+                        } else if replacement.content.as_bytes() != asset.content.as_bytes() {
+                            asset.map = None;
+                        }
+                        asset.content = replacement.content;
+                        if let Some(replaced_type) = replacement.asset_type {
+                            asset.asset_type = replaced_type;
                         }
-                        let next_transformer = index + 1;
-                        if transformers.index(&asset.transformer_pattern, next_transformer).is_none() {
-                            queue.push(Action::Done(id));
-                            continue;
-                        } 
-                        queue.push(Action::TransformContents(id, next_transformer));
+                    }
+                    TransformerResult::Err(err) => {
+                        self.add_transformer_diagnostic(&mut asset, &transformer.get_name(), Severity::Error, err);
+                        return;
+                    }
+                }
+
+                self.add_children(dependencies, inline_assets, queue);
+
+                if self.options.profiling {
+                    profiler_end(&format!("TransformContents - {}", transformer.get_name()));
+                }
 
+                if asset.asset_type != asset_type {
+                    let rematch_result = self.rematch_transformers(id, &mut asset, asset_type);
+                    if rematch_result.is_err() {
+                        let mut error = BuildError::new(
+                            &asset.file_path,
+                            BuildStage::TransformContents,
+                            &rematch_result.err().unwrap(),
+                        );
+                        error.transformer = Some(transformer.get_name());
+                        self.diagnostics.lock().unwrap().push(error);
+                        return;
                     }
-                    Action::Done(_) => {
-                        in_pipeline.fetch_sub(1, Ordering::Relaxed);
+                    if rematch_result.unwrap() {
+                        queue.spawn(Action::TransformContents(id, 0));
+                        return;
                     }
                 }
-                // hits += 1;
-            }
-            if args.log_level.is_verbose() {
-                println!("T{}: CLOSED", t);
+
+                let next_transformer = index + 1;
+                if self.transformers.index(&asset.transformer_pattern, next_transformer).is_none() {
+                    return;
+                }
+                queue.spawn(Action::TransformContents(id, next_transformer));
             }
-        }));
+        }
     }
 
-    for handle in handles {
-        handle.join().unwrap();
+    /// Matches the pipeline for an asset's new type, as if the file had the type's extension.
+    /// Returns true when the asset moved to a different pipeline, which then runs from the start.
+    /// Changing back to a type the asset already had is an error, as the pipelines would loop
+    fn rematch_transformers(&self, id: usize, asset: &mut Asset, previous_type: String) -> Result<bool, String> {
+        asset.previous_types.push(previous_type);
+        if asset.previous_types.contains(&asset.asset_type) || asset.previous_types.len() > MAX_TYPE_CHANGES {
+            return Err(format!(
+                "Asset type changed in a loop: {} -> {}",
+                asset.previous_types.join(" -> "),
+                asset.asset_type,
+            ));
+        }
+
+        let pattern_match = self.transformers.match_pattern(
+            &asset.file_path.with_extension(&asset.asset_type),
+            &self.options.resolver.project_root,
+            asset.pipeline.as_deref(),
+        )?;
+        for reporter in self.reporters {
+            reporter.report(&ReporterEvent::TransformersMatched(id, &pattern_match));
+        }
+
+        if pattern_match.pattern == asset.transformer_pattern {
+            return Ok(false);
+        }
+        asset.transformer_pattern = pattern_match.pattern;
+        return Ok(true);
     }
 
-    if args.log_level.is_verbose() {
-        println!("");
+    /// Records a problem a transformer found, along with the content its locations point into
+    fn add_transformer_diagnostic(&self, asset: &mut Asset, transformer: &str, severity: Severity, diagnostic: TransformerError) {
+        let mut error = BuildError::new(&asset.file_path, BuildStage::TransformContents, &diagnostic.message);
+        error.severity = severity;
+        error.transformer = Some(transformer.to_string());
+        error.loc = diagnostic.loc;
+        error.highlights = diagnostic.highlights;
+        error.hints = diagnostic.hints;
+        error.documentation_url = diagnostic.documentation_url;
+        if error.get_highlights().len() != 0 {
+            error.code = asset.content.as_str().ok().map(String::from);
+        }
+        self.diagnostics.lock().unwrap().push(error);
     }
 
-    profiler_end_transformations("Transformation");
+    /// Environment of entries, dependencies inherit theirs from the asset that depends on them
+    fn entry_env(&self) -> Environment {
+        let mut env = Environment::new(EnvironmentContext::Browser, self.options.mode.clone());
+        env.source_maps = self.options.source_maps_enabled();
+        env.targets = self.options.targets.clone();
+        return env;
+    }
+
+    /// Adds the dependencies a transformer kept to the graph and creates the assets they point to,
+    /// external dependencies are only recorded
+    fn add_children(
+        &self,
+        dependencies: Vec<Dependency>,
+        inline_assets: Vec<(Dependency, InlineAsset)>,
+        queue: &dyn Spawner<Action>,
+    ) {
+        for dependency in dependencies {
+            let is_external = dependency.is_external;
+            let dependency_id = self.graph.add_dependency(dependency);
+            if !is_external {
+                queue.spawn(Action::CreateAsset(dependency_id));
+            }
+        }
+        for (dependency, inline_asset) in inline_assets {
+            let dependency_id = self.graph.add_dependency(dependency);
+            self.create_asset(dependency_id, Some(inline_asset), queue);
+        }
+    }
+
+    /// Resolves a dependency to the asset with the same identity, creating it
+    /// if this is the first time it is seen. The index entry is held while the
+    /// asset is pushed so concurrent dependencies can't create duplicates.
+    /// Inline assets skip reading and start with the content they were given
+    fn create_asset(&self, dependency_id: usize, inline_asset: Option<InlineAsset>, queue: &dyn Spawner<Action>) {
+        let is_inline = inline_asset.is_some();
+        let dependency = self.graph.get_dependency(dependency_id);
+        let key = AssetKey {
+            file_path: canonicalize_path(&dependency.file_path),
+            query: dependency.query,
+            pipeline: dependency.pipeline,
+            unique_key: dependency.unique_key,
+            env: dependency.env.unwrap_or_else(|| self.entry_env()),
+        };
+
+        let id = match self.assets_index.entry(key) {
+            Entry::Occupied(entry) => {
+                self.graph.resolve_dependency(dependency_id, *entry.get());
+                return;
+            }
+            Entry::Vacant(entry) => {
+                let mut asset = Asset::new(entry.key());
+                if let Some(inline_asset) = inline_asset {
+                    asset.asset_type = inline_asset.asset_type;
+                    asset.content = Content::from(inline_asset.content);
+                }
+                let id = self.assets.push(asset);
+                entry.insert(id);
+                id
+            }
+        };
+
+        self.graph.resolve_dependency(dependency_id, id);
+        if is_inline {
+            queue.spawn(Action::AssignTransformers(id));
+            return;
+        }
+        queue.spawn(Action::ReadContents(id));
+    }
 }
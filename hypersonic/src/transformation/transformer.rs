@@ -3,7 +3,7 @@ use super::TransformerResult;
 
 use crate::core::Asset;
 
-pub trait Transformer {
+pub trait Transformer: Send + Sync {
     fn transform(&self, ctx: &TransformerContext, asset: &mut Asset) -> TransformerResult; 
     fn get_name(&self) -> String {
         return String::from("Unnamed Transformer");
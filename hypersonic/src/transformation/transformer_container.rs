@@ -1,51 +1,206 @@
-use std::{collections::HashMap, path::PathBuf, ops::Index};
+use std::{cmp::Reverse, fmt, path::{Path, PathBuf}};
 use glob_match::glob_match;
+use path_slash::PathBufExt;
+use crate::core::split_pipeline;
 use super::Transformer;
 
+/// Transformer pipelines keyed by glob pattern, in the order they were added.
+///
+/// Patterns containing a `/` are matched against the project-relative path,
+/// other patterns against the file name. Patterns can be prefixed with a named
+/// pipeline (`url:*.png`), those only match assets requested through that
+/// pipeline and take priority over unnamed patterns.
 pub struct TransformerContainer {
-    transformers: HashMap<String, Vec<Box<dyn Transformer>>>,
+    transformers: Vec<(String, Vec<Box<dyn Transformer>>)>,
+}
+
+/// Which pattern an asset was assigned and why
+#[derive(Clone, Debug)]
+pub struct PatternMatch {
+    pub pattern: String,
+    /// The path the pattern was matched against
+    pub matched_path: String,
+    /// Other patterns that matched, from most to least specific
+    pub candidates: Vec<String>,
 }
 
 impl TransformerContainer {
     pub fn new() -> Self {
         return TransformerContainer {
-          transformers: HashMap::new(),
+          transformers: Vec::new(),
         };
     }
 
     pub fn add(&mut self, pattern: &str, transformer: Box<dyn Transformer>) { 
-        if !self.transformers.contains_key(pattern) {
-            self.transformers.insert(pattern.to_owned(), Vec::new());
+        let existing = self.transformers.iter_mut().find(|(p, _)| p == pattern);
+        if let Some((_, transformers)) = existing {
+            transformers.push(transformer);
+            return;
         }
-
-        let transformers = self.transformers.get_mut(pattern).unwrap();
-        transformers.push(transformer);
+        self.transformers.push((pattern.to_owned(), vec![transformer]));
     }
 
-    pub fn match_pattern(&self, file_path: &PathBuf) -> Result<String, ()> {
-        if !file_path.is_file() {
-            return Err(());
+    /// Picks the most specific pattern matching the path, ties go to the pattern added first.
+    /// The path doesn't need to exist, so assets can be matched by a type they were changed to
+    pub fn match_pattern(
+        &self,
+        file_path: &PathBuf,
+        project_root: &Path,
+        pipeline: Option<&str>,
+    ) -> Result<PatternMatch, String> {
+        let relative_path = match pathdiff::diff_paths(file_path, project_root) {
+            Some(relative) if !relative.starts_with("..") => relative,
+            _ => file_path.clone(),
+        };
+        let relative_path = relative_path.to_slash_lossy().to_string();
+        let Some(file_name) = file_path.file_name() else {
+            return Err(format!("No file name in \"{}\"", relative_path));
+        };
+        let file_name = file_name.to_string_lossy();
+
+        let mut matches = Vec::<(Specificity, usize)>::new();
+        for (index, (pattern, _)) in self.transformers.iter().enumerate() {
+            let (pattern_pipeline, glob) = split_pipeline(pattern);
+            if pattern_pipeline.is_some() && pattern_pipeline.as_deref() != pipeline {
+                continue;
+            }
+
+            let is_path = glob.contains('/');
+            let target = if is_path { relative_path.as_str() } else { &file_name };
+            if glob_match(glob, target) {
+                matches.push((Specificity::new(pattern_pipeline.is_some(), glob), index));
+            }
         }
-        let file_name = file_path.file_name().unwrap().to_str().unwrap();
 
-        for (pattern, _) in self.transformers.iter() {
-            if glob_match(pattern.as_str(), file_name) {
-                return Ok(pattern.clone());
+        // Highest specificity first, then declaration order
+        matches.sort_by(|(a, a_index), (b, b_index)| b.cmp(a).then(a_index.cmp(b_index)));
+
+        if matches.len() == 0 {
+            let mut message = format!("No transformers match \"{}\"", relative_path);
+            if let Some(pipeline) = pipeline {
+                message.push_str(&format!(" in the \"{}\" pipeline", pipeline));
             }
+            return Err(message);
         }
-        return Err(());
+
+        let patterns: Vec<String> = matches
+            .iter()
+            .map(|(_, index)| self.transformers[*index].0.clone())
+            .collect();
+
+        return Ok(PatternMatch {
+            pattern: patterns[0].clone(),
+            matched_path: relative_path,
+            candidates: patterns[1..].to_vec(),
+        });
     }
 
     pub fn index(&self, pattern: &str, index: usize) -> Option<&Box<dyn Transformer>> {
-        let vec_opt = self.transformers.get(pattern);
+        let vec_opt = self.transformers.iter().find(|(p, _)| p == pattern);
         if vec_opt.is_none() {
             return None;
         }
-        let list = vec_opt.unwrap();
-        if index >= list.len() {
-            return None;
+        let (_, list) = vec_opt.unwrap();
+        return list.get(index);
+    }
+}
+
+impl fmt::Display for PatternMatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\"{}\" matched \"{}\"", self.matched_path, self.pattern)?;
+        if self.candidates.len() != 0 {
+            write!(f, " (more specific than \"{}\")", self.candidates.join("\", \""))?;
         }
-        let transformer = list.index(index);
-        return Some(transformer);
+        return Ok(());
+    }
+}
+
+/// Ranks patterns so the one that says most about a path wins:
+/// named pipelines, then directory scoped patterns, then the most literal characters
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct Specificity {
+    is_named: bool,
+    is_path: bool,
+    literals: usize,
+    /// Fewer wildcards ranks higher
+    wildcards: Reverse<usize>,
+}
+
+impl Specificity {
+    fn new(is_named: bool, glob: &str) -> Self {
+        let wildcards = glob.chars().filter(|c| matches!(c, '*' | '?' | '[' | '{')).count();
+        let literals = glob.chars().filter(|c| !matches!(c, '*' | '?' | '[' | ']' | '{' | '}' | ',')).count();
+        return Specificity {
+            is_named,
+            is_path: glob.contains('/'),
+            literals,
+            wildcards: Reverse(wildcards),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::default_plugins::DefaultNoopTransformer;
+
+    use super::*;
+
+    fn fixtures() -> PathBuf {
+        return Path::new(env!("CARGO_MANIFEST_DIR")).join("../fixtures/basic-nested");
+    }
+
+    fn container(patterns: &[&str]) -> TransformerContainer {
+        let mut container = TransformerContainer::new();
+        for pattern in patterns {
+            container.add(pattern, Box::new(DefaultNoopTransformer::new()));
+        }
+        return container;
+    }
+
+    #[test]
+    fn test_most_specific_pattern_wins() {
+        let file_path = fixtures().join("scripts/a.js");
+
+        // The result must not depend on the order patterns were added
+        for patterns in [
+            ["*.js", "scripts/**/*.js", "scripts/a.js", "*"],
+            ["*", "scripts/a.js", "scripts/**/*.js", "*.js"],
+        ] {
+            let result = container(&patterns).match_pattern(&file_path, &fixtures(), None).unwrap();
+            assert_eq!(result.pattern, "scripts/a.js");
+            assert_eq!(result.matched_path, "scripts/a.js");
+            assert_eq!(result.candidates, vec!["scripts/**/*.js", "*.js", "*"]);
+        }
+    }
+
+    #[test]
+    fn test_ties_use_declaration_order() {
+        let file_path = fixtures().join("scripts/a.js");
+        let result = container(&["*.js", "a.j*"]).match_pattern(&file_path, &fixtures(), None).unwrap();
+        assert_eq!(result.pattern, "*.js");
+        let result = container(&["a.j*", "*.js"]).match_pattern(&file_path, &fixtures(), None).unwrap();
+        assert_eq!(result.pattern, "a.j*");
+    }
+
+    #[test]
+    fn test_path_without_file_name() {
+        let err = container(&["*"]).match_pattern(&PathBuf::from("/"), &fixtures(), None).err().unwrap();
+        assert_eq!(err, "No file name in \"/\"");
+    }
+
+    #[test]
+    fn test_named_pipelines() {
+        let file_path = fixtures().join("scripts/a.js");
+        let container = container(&["*.js", "url:*"]);
+
+        let result = container.match_pattern(&file_path, &fixtures(), Some("url")).unwrap();
+        assert_eq!(result.pattern, "url:*");
+
+        // Named patterns are ignored without the pipeline, and unnamed ones are the fallback
+        assert_eq!(container.match_pattern(&file_path, &fixtures(), None).unwrap().pattern, "*.js");
+        assert_eq!(container.match_pattern(&file_path, &fixtures(), Some("raw")).unwrap().pattern, "*.js");
+
+        let err = container.match_pattern(&fixtures().join("index.html"), &fixtures(), None).err().unwrap();
+        assert_eq!(err, "No transformers match \"index.html\"");
     }
 }
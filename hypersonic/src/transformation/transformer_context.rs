@@ -1,21 +1,169 @@
-use std::path::PathBuf;
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use crate::utils::Queue;
+use crate::core::{AssetGraph, Dependency, DependencyKind, Environment, InlineAsset, Invalidation, Severity};
 use crate::platform::LogLevel;
+use crate::resolver::{Resolution, Resolver};
 
-use super::actions::Action;
+use super::{ConfigCache, LoadedConfig, TransformerError};
 
 pub struct TransformerContext {
-  pub queue: Queue::<Action>,
+  pub asset_id: usize,
+  pub env: Environment,
+  pub graph: Arc<AssetGraph>,
   pub log_level: LogLevel,
+  pub resolver: Arc<Resolver>,
+  pub config_cache: Arc<ConfigCache>,
+  dependencies: RefCell<Vec<Dependency>>,
+  inline_assets: RefCell<Vec<(Dependency, InlineAsset)>>,
+  invalidations: RefCell<Vec<Invalidation>>,
+  diagnostics: RefCell<Vec<(Severity, TransformerError)>>,
 }
 
 impl TransformerContext {
+  pub fn new(
+    asset_id: usize,
+    env: Environment,
+    graph: Arc<AssetGraph>,
+    log_level: LogLevel,
+    resolver: Arc<Resolver>,
+    config_cache: Arc<ConfigCache>,
+  ) -> Self {
+    return TransformerContext {
+      asset_id,
+      env,
+      graph,
+      log_level,
+      resolver,
+      config_cache,
+      dependencies: RefCell::new(Vec::new()),
+      inline_assets: RefCell::new(Vec::new()),
+      invalidations: RefCell::new(Vec::new()),
+      diagnostics: RefCell::new(Vec::new()),
+    };
+  }
+
   pub fn get_log_level(&self) -> LogLevel {
       return self.log_level.clone();
   }
 
-  pub fn add_dependency(&self, file_path: PathBuf) {
-    self.queue.push(Action::CreateAsset(file_path))
+  pub fn get_env(&self) -> &Environment {
+    return &self.env;
+  }
+
+  /// Resolves a specifier imported by the file at `from` through a dependency of `kind`,
+  /// for the environment the dependency runs in, using the build's shared resolver
+  pub fn resolve(&self, specifier: &str, from: &PathBuf, kind: &DependencyKind) -> Result<Resolution, String> {
+    return self.resolver.resolve(specifier, from, kind, &self.env.for_dependency(kind));
+  }
+
+  /// Finds the closest of `file_names` in the directories from `from` (the current asset's file)
+  /// up to the project root, parsed as JSON with comments, eg `ctx.load_config(&asset.file_path, &["tsconfig.json"])`.
+  /// Every path looked at is recorded as an invalidation of the asset
+  pub fn load_config(&self, from: &Path, file_names: &[&str]) -> Result<Option<LoadedConfig>, String> {
+    return self.config_cache.load(
+      from,
+      file_names,
+      &self.resolver.options.project_root,
+      &mut self.invalidations.borrow_mut(),
+    );
+  }
+
+  /// Records a file the result depends on, for files read without `load_config`
+  pub fn add_invalidation(&self, invalidation: Invalidation) {
+    self.invalidations.borrow_mut().push(invalidation);
+  }
+
+  pub fn add_dependency(&self, dependency: Dependency) {
+    let mut dependency = dependency;
+    dependency.source_asset_id = Some(self.asset_id);
+    if dependency.env.is_none() {
+      dependency.env = Some(self.env.for_dependency(&dependency.kind));
+    }
+    self.dependencies.borrow_mut().push(dependency);
+  }
+
+  /// Adds `dependency` on what its specifier resolved to. Files get an asset of their own,
+  /// externals are only recorded and empty or global modules become a JS module without a
+  /// file. Those don't depend on the importer, so their unique key (eg `empty:fs`) is their
+  /// virtual path too and one is shared by every importer in an environment
+  pub fn add_resolved_dependency(&self, dependency: Dependency, resolution: Resolution) {
+    let mut dependency = dependency;
+    match resolution {
+      Resolution::Path(file_path, query) => {
+        dependency.file_path = file_path;
+        dependency.query = query;
+        self.add_dependency(dependency);
+      }
+      // The import stays in the output for the runtime to load
+      Resolution::External => {
+        dependency.is_external = true;
+        self.add_dependency(dependency);
+      }
+      Resolution::Empty => {
+        let key = format!("empty:{}", dependency.specifier);
+        dependency.file_path = PathBuf::from(&key);
+        self.add_inline_dependency(dependency, InlineAsset::new(&key, "js", ""));
+      }
+      Resolution::Global(global) => {
+        let key = format!("global:{}", dependency.specifier);
+        let content = format!("module.exports = {};", global);
+        dependency.file_path = PathBuf::from(&key);
+        self.add_inline_dependency(dependency, InlineAsset::new(&key, "js", &content));
+      }
+    }
+  }
+
+  /// Adds a child asset with the given content, located at `from` (the current asset's file).
+  /// It runs through the pipeline for its type without reading from disk
+  pub fn add_inline_asset(&self, from: &PathBuf, inline_asset: InlineAsset) {
+    let mut dependency = Dependency::new(&inline_asset.unique_key, inline_asset.kind.clone(), from.clone());
+    dependency.env = inline_asset.env.clone();
+    self.add_inline_dependency(dependency, inline_asset);
+  }
+
+  /// Like `add_inline_asset` with a dependency built by the transformer, so it keeps the
+  /// specifier and location of the import. The asset is identified by the dependency's
+  /// `file_path` and the inline asset's `unique_key`
+  pub fn add_inline_dependency(&self, dependency: Dependency, inline_asset: InlineAsset) {
+    let mut dependency = dependency;
+    dependency.unique_key = Some(inline_asset.unique_key.clone());
+    dependency.source_asset_id = Some(self.asset_id);
+    if dependency.env.is_none() {
+      dependency.env = Some(self.env.for_dependency(&dependency.kind));
+    }
+    self.inline_assets.borrow_mut().push((dependency, inline_asset));
+  }
+
+  /// Dependencies added by the transformer. The orchestrator adds them to the graph once
+  /// the transformer returns, unless it skipped or failed
+  pub fn take_dependencies(&self) -> Vec<Dependency> {
+    return self.dependencies.borrow_mut().drain(..).collect();
+  }
+
+  /// Inline assets with the dependency on them, created by the orchestrator like `take_dependencies`
+  pub fn take_inline_assets(&self) -> Vec<(Dependency, InlineAsset)> {
+    return self.inline_assets.borrow_mut().drain(..).collect();
+  }
+
+  /// Reports a problem without stopping the transformer. Errors still fail the build,
+  /// for problems after the first when the transformer returns `Err`
+  pub fn add_diagnostic(&self, severity: Severity, diagnostic: TransformerError) {
+    self.diagnostics.borrow_mut().push((severity, diagnostic));
+  }
+
+  pub fn add_warning(&self, warning: TransformerError) {
+    self.add_diagnostic(Severity::Warning, warning);
+  }
+
+  /// Diagnostics reported by the transformer, added to the build result by the orchestrator once it returns
+  pub fn take_diagnostics(&self) -> Vec<(Severity, TransformerError)> {
+    return self.diagnostics.borrow_mut().drain(..).collect();
+  }
+
+  /// Files the transformer depends on, added to the asset by the orchestrator once it returns
+  pub fn take_invalidations(&self) -> Vec<Invalidation> {
+    return self.invalidations.borrow_mut().drain(..).collect();
   }
 }
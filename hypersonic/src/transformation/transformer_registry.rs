@@ -0,0 +1,37 @@
+use std::collections::HashMap;
+
+use super::Transformer;
+
+pub type TransformerFactory = Box<dyn Fn() -> Box<dyn Transformer> + Send + Sync>;
+
+/// Transformers that can be referred to by name from a config file
+pub struct TransformerRegistry {
+    factories: HashMap<String, TransformerFactory>,
+}
+
+impl TransformerRegistry {
+    pub fn new() -> Self {
+        return TransformerRegistry {
+            factories: HashMap::new(),
+        };
+    }
+
+    pub fn register(&mut self, name: &str, factory: TransformerFactory) {
+        self.factories.insert(name.to_string(), factory);
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        return self.factories.contains_key(name);
+    }
+
+    pub fn create(&self, name: &str) -> Option<Box<dyn Transformer>> {
+        let factory = self.factories.get(name)?;
+        return Some(factory());
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.factories.keys().cloned().collect();
+        names.sort();
+        return names;
+    }
+}
@@ -1,7 +1,60 @@
+use crate::core::{CodeHighlight, Content, SourceLocation, SourceMap};
+
 pub enum TransformerResult {
+    /// Done with the asset, run the next transformer
     Continue,
+    /// The transformer doesn't apply to this asset and made no changes.
+    /// Dependencies it added are dropped and the next transformer runs
+    Skip,
+    /// Stop the pipeline, the asset is complete with its current content
     Break,
-    Err(String),
+    /// Swap the asset's content and metadata in one step, then run the next transformer.
+    /// Nothing is applied if the transformer fails, unlike edits made through `&mut Asset`
+    Replace(Replacement),
+    Err(TransformerError),
+}
+
+pub struct Replacement {
+    pub content: Content,
+    /// None keeps the asset's type
+    pub asset_type: Option<String>,
+    /// Maps `content` to the content the transformer was given, it is composed
+    /// with the asset's map. None drops the asset's map if the content changed,
+    /// as it would point into content that no longer exists
+    pub map: Option<SourceMap>,
+}
+
+impl Replacement {
+    pub fn new(content: Content) -> Self {
+        return Replacement {
+            content,
+            asset_type: None,
+            map: None,
+        };
+    }
+}
+
+/// A problem found by a transformer, returned with `Err` or reported through the context
+#[derive(Clone, Debug)]
+pub struct TransformerError {
+    pub message: String,
+    pub loc: Option<SourceLocation>,
+    /// Ranges to show in the code frame, `loc` is shown when there are none
+    pub highlights: Vec<CodeHighlight>,
+    pub hints: Vec<String>,
+    pub documentation_url: Option<String>,
+}
+
+impl TransformerError {
+    pub fn new(message: &str) -> Self {
+        return TransformerError {
+            message: message.to_string(),
+            loc: None,
+            highlights: Vec::new(),
+            hints: Vec::new(),
+            documentation_url: None,
+        };
+    }
 }
 
 impl TransformerResult {
@@ -12,10 +65,20 @@ impl TransformerResult {
         }
     }
 
-    pub fn err(&self) -> String {
+    pub fn err(&self) -> TransformerError {
         return match self {
             TransformerResult::Err(e) => e.clone(),
             _ => panic!("Tried to get error on transformation result that wasn't an error"),
         }
     }
+
+    pub fn get_name(&self) -> &str {
+        return match self {
+            TransformerResult::Continue => "Continue",
+            TransformerResult::Skip => "Skip",
+            TransformerResult::Break => "Break",
+            TransformerResult::Replace(_) => "Replace",
+            TransformerResult::Err(_) => "Err",
+        };
+    }
 }
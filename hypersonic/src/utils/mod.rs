@@ -1,11 +1,13 @@
 #![allow(dead_code)]
 
-mod container;
-mod hash;
+mod path;
 mod profiler;
 mod queue;
+mod segmented_container;
+mod work_stealing_queue;
 
-pub use crate::utils::container::*;
-pub use crate::utils::hash::*;
+pub use crate::utils::path::*;
 pub use crate::utils::profiler::*;
 pub use crate::utils::queue::*;
+pub use crate::utils::segmented_container::*;
+pub use crate::utils::work_stealing_queue::*;
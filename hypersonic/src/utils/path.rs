@@ -0,0 +1,22 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Resolves symlinks, "." and ".." segments. Paths that don't exist
+/// on disk are only normalized lexically
+pub fn canonicalize_path(path: &Path) -> PathBuf {
+  if let Ok(canonical) = fs::canonicalize(path) {
+    return canonical;
+  }
+
+  let mut normalized = PathBuf::new();
+  for component in path.components() {
+    match component {
+      Component::CurDir => {}
+      Component::ParentDir => {
+        normalized.pop();
+      }
+      _ => normalized.push(component.as_os_str()),
+    }
+  }
+  return normalized;
+}
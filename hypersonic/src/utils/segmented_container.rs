@@ -0,0 +1,233 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::thread;
+
+use arc_swap::ArcSwapOption;
+
+const FIRST_SEGMENT_BITS: u32 = 6;
+const SEGMENTS: usize = (usize::BITS - FIRST_SEGMENT_BITS) as usize;
+
+type Segment<T> = Box<[OnceLock<Slot<T>>]>;
+
+/// Append-only container that grows in segments of doubling size.
+///
+/// Segments are allocated on first use and never move, so an index handed
+/// out by `push` stays valid for the lifetime of the container. Growing takes
+/// no lock, the segment list and each slot are written once through `OnceLock`.
+///
+/// Reads take no lock either, each slot holds an `Arc` that readers clone.
+/// `claim` takes the item out of its slot for exclusive access and puts it back
+/// when the claim is dropped, only claims of the same index wait on each other.
+pub struct SegmentedContainer<T> {
+    segments: Box<[OnceLock<Segment<T>>]>,
+    reserved: AtomicUsize,
+    size: AtomicUsize,
+}
+
+struct Slot<T> {
+    item: ArcSwapOption<T>,
+    /// Held for the whole of a claim
+    writer: Mutex<()>,
+}
+
+/// Exclusive access to an item, returned to its slot on drop
+pub struct Claim<'a, T> {
+    slot: &'a Slot<T>,
+    item: Option<T>,
+    _writer: MutexGuard<'a, ()>,
+}
+
+impl<T> SegmentedContainer<T> {
+    pub fn new() -> Arc<SegmentedContainer<T>> {
+        let mut segments = Vec::<OnceLock<Segment<T>>>::with_capacity(SEGMENTS);
+        for _ in 0..SEGMENTS {
+            segments.push(OnceLock::new());
+        }
+
+        return Arc::new(SegmentedContainer {
+            segments: segments.into_boxed_slice(),
+            reserved: AtomicUsize::new(0),
+            size: AtomicUsize::new(0),
+        });
+    }
+
+    /// Number of items that have finished being pushed
+    pub fn len(&self) -> usize {
+        return self.size.load(Ordering::Acquire);
+    }
+
+    pub fn push(&self, item: T) -> usize {
+        let index = self.reserved.fetch_add(1, Ordering::Relaxed);
+        let Some((segment, offset)) = locate(index) else {
+            panic!("PANIC: Container is full at index {}", index);
+        };
+
+        let slots = self.segments[segment].get_or_init(|| allocate_segment(segment));
+        let slot = Slot {
+            item: ArcSwapOption::from(Some(Arc::new(item))),
+            writer: Mutex::new(()),
+        };
+        if slots[offset].set(slot).is_err() {
+            panic!("PANIC: Container slot {} was written twice", index);
+        }
+
+        self.size.fetch_add(1, Ordering::Release);
+        return index;
+    }
+
+    /// The item as it was last put back, None while it is claimed
+    pub fn read(&self, index: usize) -> Option<Arc<T>> {
+        let slot = self.slot(index)?;
+        return slot.item.load_full();
+    }
+
+    /// Exclusive access to an item, blocks until other claims of it are dropped
+    /// and readers have let go of it
+    pub fn claim(&self, index: usize) -> Option<Claim<'_, T>> {
+        let slot = self.slot(index)?;
+        let writer = slot.writer.lock().unwrap();
+
+        let mut item = slot.item.swap(None)?;
+        let item = loop {
+            match Arc::try_unwrap(item) {
+                Ok(item) => break item,
+                Err(shared) => {
+                    item = shared;
+                    thread::yield_now();
+                }
+            }
+        };
+
+        return Some(Claim {
+            slot,
+            item: Some(item),
+            _writer: writer,
+        });
+    }
+
+    fn slot(&self, index: usize) -> Option<&Slot<T>> {
+        let (segment, offset) = locate(index)?;
+        return self.segments[segment].get()?[offset].get();
+    }
+}
+
+impl<T> Deref for Claim<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        return self.item.as_ref().unwrap();
+    }
+}
+
+impl<T> DerefMut for Claim<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        return self.item.as_mut().unwrap();
+    }
+}
+
+impl<T> Drop for Claim<'_, T> {
+    fn drop(&mut self) {
+        if let Some(item) = self.item.take() {
+            self.slot.item.store(Some(Arc::new(item)));
+        }
+    }
+}
+
+fn allocate_segment<T>(segment: usize) -> Segment<T> {
+    let capacity = 1 << (segment + FIRST_SEGMENT_BITS as usize);
+    let mut slots = Vec::<OnceLock<Slot<T>>>::with_capacity(capacity);
+    for _ in 0..capacity {
+        slots.push(OnceLock::new());
+    }
+    return slots.into_boxed_slice();
+}
+
+/// Segment n holds 2^(n + FIRST_SEGMENT_BITS) items, starting after all previous segments.
+/// None for the last few indices, which would overflow the position
+fn locate(index: usize) -> Option<(usize, usize)> {
+    let position = index.checked_add(1 << FIRST_SEGMENT_BITS)?;
+    let bit = (usize::BITS - 1 - position.leading_zeros()) as usize;
+    let segment = bit - FIRST_SEGMENT_BITS as usize;
+    return Some((segment, position - (1 << bit)));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_locate() {
+        assert_eq!(locate(0), Some((0, 0)));
+        assert_eq!(locate(63), Some((0, 63)));
+        assert_eq!(locate(64), Some((1, 0)));
+        assert_eq!(locate(191), Some((1, 127)));
+        assert_eq!(locate(192), Some((2, 0)));
+        assert_eq!(locate(usize::MAX - 64), Some((SEGMENTS - 1, usize::MAX >> 1)));
+        assert_eq!(locate(usize::MAX - 63), None);
+    }
+
+    #[test]
+    fn test_read_out_of_range() {
+        let container = SegmentedContainer::<usize>::new();
+        container.push(1);
+        assert!(container.read(usize::MAX).is_none());
+        assert!(container.claim(usize::MAX - 64).is_none());
+    }
+
+    #[test]
+    fn test_push_grows() {
+        let container = SegmentedContainer::<usize>::new();
+        for i in 0..1000 {
+            assert_eq!(container.push(i * 2), i);
+        }
+        assert_eq!(container.len(), 1000);
+        assert_eq!(*container.read(999).unwrap(), 1998);
+        assert!(container.read(1000).is_none());
+
+        *container.claim(10).unwrap() = 1;
+        assert_eq!(*container.read(10).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_claim_waits_for_readers() {
+        let container = SegmentedContainer::<usize>::new();
+        container.push(1);
+
+        let reader = container.read(0).unwrap();
+        let claimer = {
+            let container = container.clone();
+            thread::spawn(move || {
+                *container.claim(0).unwrap() += 1;
+            })
+        };
+
+        // The reader keeps what it read, the claim can only go ahead once it is dropped
+        thread::sleep(std::time::Duration::from_millis(50));
+        assert_eq!(*reader, 1);
+        drop(reader);
+        claimer.join().unwrap();
+        assert_eq!(*container.read(0).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_concurrent_push() {
+        let container = SegmentedContainer::<usize>::new();
+        let mut handles = Vec::new();
+        for t in 0..4 {
+            let container = container.clone();
+            handles.push(thread::spawn(move || {
+                for i in 0..500 {
+                    let index = container.push(t * 1000 + i);
+                    assert_eq!(*container.read(index).unwrap(), t * 1000 + i);
+                }
+            }));
+        }
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(container.len(), 2000);
+    }
+}
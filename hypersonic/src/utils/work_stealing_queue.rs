@@ -0,0 +1,188 @@
+use std::iter;
+use std::sync::atomic::{fence, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+
+use crossbeam_deque::{Injector, Stealer, Worker};
+
+/// Work-stealing queue with one local deque per worker thread.
+///
+/// Every pushed item counts as pending until a worker calls `complete` for it.
+/// Items pushed while handling another item are counted before the parent
+/// completes, so the pending count only reaches zero once all work is drained.
+pub struct WorkStealingQueue<T> {
+    injector: Injector<T>,
+    stealers: Box<[Stealer<T>]>,
+    pending: AtomicUsize,
+    sleeping: AtomicUsize,
+    lock: Mutex<()>,
+    condvar: Condvar,
+}
+
+pub struct WorkStealingHandle<T> {
+    index: usize,
+    local: Worker<T>,
+    queue: Arc<WorkStealingQueue<T>>,
+}
+
+impl<T> WorkStealingQueue<T> {
+    pub fn new(workers: usize) -> (Arc<Self>, Vec<WorkStealingHandle<T>>) {
+        let locals: Vec<Worker<T>> = (0..workers).map(|_| Worker::new_lifo()).collect();
+        let stealers: Vec<Stealer<T>> = locals.iter().map(|w| w.stealer()).collect();
+
+        let queue = Arc::new(WorkStealingQueue::<T> {
+            injector: Injector::new(),
+            stealers: stealers.into_boxed_slice(),
+            pending: AtomicUsize::new(0),
+            sleeping: AtomicUsize::new(0),
+            lock: Mutex::new(()),
+            condvar: Condvar::new(),
+        });
+
+        let handles = locals
+            .into_iter()
+            .enumerate()
+            .map(|(index, local)| WorkStealingHandle {
+                index,
+                local,
+                queue: queue.clone(),
+            })
+            .collect();
+
+        return (queue, handles);
+    }
+
+    /// Push from outside of a worker thread
+    pub fn push(&self, value: T) {
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        self.injector.push(value);
+        self.notify_one();
+    }
+
+    pub fn pending(&self) -> usize {
+        return self.pending.load(Ordering::SeqCst);
+    }
+
+    fn has_work(&self) -> bool {
+        return !self.injector.is_empty() || self.stealers.iter().any(|s| !s.is_empty());
+    }
+
+    fn notify_one(&self) {
+        // Pairs with the fence in `next` so either the pusher sees the
+        // sleeper or the sleeper sees the pushed item
+        fence(Ordering::SeqCst);
+        if self.sleeping.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+        let _guard = self.lock.lock().unwrap();
+        self.condvar.notify_one();
+    }
+
+    fn notify_all(&self) {
+        let _guard = self.lock.lock().unwrap();
+        self.condvar.notify_all();
+    }
+}
+
+impl<T> WorkStealingHandle<T> {
+    pub fn index(&self) -> usize {
+        return self.index;
+    }
+
+    /// Push onto this worker's deque, idle workers may steal it
+    pub fn push(&self, value: T) {
+        self.queue.pending.fetch_add(1, Ordering::SeqCst);
+        self.local.push(value);
+        self.queue.notify_one();
+    }
+
+    /// Blocks until an item is available.
+    /// Returns None once every pushed item has been completed
+    pub fn next(&self) -> Option<T> {
+        loop {
+            if let Some(value) = self.find() {
+                return Some(value);
+            }
+
+            let guard = self.queue.lock.lock().unwrap();
+            if self.queue.pending() == 0 {
+                return None;
+            }
+
+            self.queue.sleeping.fetch_add(1, Ordering::SeqCst);
+            fence(Ordering::SeqCst);
+            if !self.queue.has_work() && self.queue.pending() != 0 {
+                drop(self.queue.condvar.wait(guard).unwrap());
+            }
+            self.queue.sleeping.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Marks an item returned by `next` as handled
+    pub fn complete(&self) {
+        let previous = self.queue.pending.fetch_sub(1, Ordering::SeqCst);
+        if previous == 1 {
+            self.queue.notify_all();
+        }
+    }
+
+    fn find(&self) -> Option<T> {
+        if let Some(value) = self.local.pop() {
+            return Some(value);
+        }
+
+        return iter::repeat_with(|| {
+            self.queue.injector
+                .steal_batch_and_pop(&self.local)
+                .or_else(|| self.queue.stealers.iter().map(|s| s.steal()).collect())
+        })
+        .find(|s| !s.is_retry())
+        .and_then(|s| s.success());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn test_drains_and_terminates() {
+        let (queue, handles) = WorkStealingQueue::<usize>::new(4);
+        let handled = Arc::new(AtomicUsize::new(0));
+
+        // Item n pushes items 0..n, so 2^n items are handled in total
+        queue.push(10);
+
+        let mut threads = Vec::new();
+        for handle in handles {
+            let handled = handled.clone();
+            threads.push(thread::spawn(move || {
+                while let Some(value) = handle.next() {
+                    for child in 0..value {
+                        handle.push(child);
+                    }
+                    handled.fetch_add(1, Ordering::SeqCst);
+                    handle.complete();
+                }
+            }));
+        }
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(handled.load(Ordering::SeqCst), 1 << 10);
+        assert_eq!(queue.pending(), 0);
+    }
+
+    #[test]
+    fn test_next_after_completion() {
+        let (queue, handles) = WorkStealingQueue::<usize>::new(2);
+        queue.push(1);
+        assert_eq!(handles[1].next(), Some(1));
+        handles[1].complete();
+        assert_eq!(handles[0].next(), None);
+    }
+}
@@ -38,19 +38,21 @@ use swc_core::ecma::transforms::{
 };
 use swc_core::ecma::visit::{FoldWith, VisitWith};
 
-use collect::{Collect, CollectResult};
+use collect::Collect;
 use decl_collector::*;
 use dependency_collector::*;
 use env_replacer::*;
 use fs::inline_fs;
 use global_replacer::GlobalReplacer;
-use hoist::{hoist, HoistResult};
+use hoist::hoist;
 use modules::esm2cjs;
 use node_replacer::NodeReplacer;
 use typeof_replacer::*;
-use utils::{CodeHighlight, Diagnostic, DiagnosticSeverity, SourceLocation, SourceType};
 
-pub use dependency_collector::DependencyDescriptor;
+pub use collect::{CollectExportedAll, CollectExportedSymbol, CollectImportedSymbol, CollectResult, ImportKind};
+pub use dependency_collector::{DependencyDescriptor, DependencyKind};
+pub use hoist::{ExportedSymbol, HoistResult, ImportedSymbol};
+pub use utils::{CodeHighlight, Diagnostic, DiagnosticSeverity, SourceLocation, SourceType};
 
 type SourceMapBuffer = Vec<(swc_core::common::BytePos, swc_core::common::LineCol)>;
 